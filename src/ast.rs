@@ -1,21 +1,40 @@
 use std::fmt::Display;
+use std::rc::Rc;
 
-use crate::token::Token;
+use crate::token::{Token, TokenType};
 
 pub enum Expr {
     Binary(BinaryExpr),
+    Call(CallExpr),
+    Get(GetExpr),
     Grouping(GroupingExpr),
+    Index(IndexExpr),
+    Interpolation(InterpolationExpr),
+    List(ListExpr),
     Literal(LiteralExpr),
+    Map(MapExpr),
+    Set(SetExpr),
+    SetIndex(SetIndexExpr),
     Unary(UnaryExpr),
+    Variable(VariableExpr),
 }
 
 impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expr::Binary(b) => b.fmt(f),
+            Expr::Call(c) => c.fmt(f),
+            Expr::Get(g) => g.fmt(f),
             Expr::Grouping(g) => g.fmt(f),
+            Expr::Index(i) => i.fmt(f),
+            Expr::Interpolation(i) => i.fmt(f),
+            Expr::List(l) => l.fmt(f),
             Expr::Literal(l) => l.fmt(f),
+            Expr::Map(m) => m.fmt(f),
+            Expr::Set(s) => s.fmt(f),
+            Expr::SetIndex(s) => s.fmt(f),
             Expr::Unary(u) => u.fmt(f),
+            Expr::Variable(v) => v.fmt(f),
         }
     }
 }
@@ -50,7 +69,77 @@ impl BinaryExpr {
 
 impl Display for BinaryExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({} {} {})", self.op, self.left, self.right)
+        write!(f, "({} {} {})", self.op.token_type(), self.left, self.right)
+    }
+}
+
+pub struct CallExpr {
+    callee: Box<Expr>,
+    paren: Box<Token>,
+    arguments: Vec<Expr>,
+}
+
+impl CallExpr {
+    pub fn new(callee: Expr, paren: Token, arguments: Vec<Expr>) -> Self {
+        Self {
+            callee: Box::new(callee),
+            paren: Box::new(paren),
+            arguments,
+        }
+    }
+
+    pub fn callee(&self) -> &Expr {
+        &self.callee
+    }
+
+    pub fn paren(&self) -> &Token {
+        &self.paren
+    }
+
+    pub fn arguments(&self) -> &[Expr] {
+        &self.arguments
+    }
+}
+
+impl Display for CallExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(call {}", self.callee)?;
+        for argument in &self.arguments {
+            write!(f, " {}", argument)?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub struct GetExpr {
+    object: Box<Expr>,
+    name: Box<Token>,
+}
+
+impl GetExpr {
+    pub fn new(object: Expr, name: Token) -> Self {
+        Self {
+            object: Box::new(object),
+            name: Box::new(name),
+        }
+    }
+
+    pub fn object(&self) -> &Expr {
+        &self.object
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn into_object(self) -> Expr {
+        *self.object
+    }
+}
+
+impl Display for GetExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(get {} {})", self.object, property_name(&self.name))
     }
 }
 
@@ -76,10 +165,114 @@ impl Display for GroupingExpr {
     }
 }
 
+pub struct IndexExpr {
+    object: Box<Expr>,
+    bracket: Box<Token>,
+    index: Box<Expr>,
+}
+
+impl IndexExpr {
+    pub fn new(object: Expr, bracket: Token, index: Expr) -> Self {
+        Self {
+            object: Box::new(object),
+            bracket: Box::new(bracket),
+            index: Box::new(index),
+        }
+    }
+
+    pub fn object(&self) -> &Expr {
+        &self.object
+    }
+
+    pub fn bracket(&self) -> &Token {
+        &self.bracket
+    }
+
+    pub fn index(&self) -> &Expr {
+        &self.index
+    }
+
+    pub fn into_operands(self) -> (Expr, Expr) {
+        (*self.object, *self.index)
+    }
+}
+
+impl Display for IndexExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(index {} {})", self.object, self.index)
+    }
+}
+
+// A string with embedded expressions, evaluated by converting each part to a
+// string and concatenating them.
+pub struct InterpolationExpr {
+    token: Box<Token>,
+    parts: Vec<Expr>,
+}
+
+impl InterpolationExpr {
+    pub fn new(token: Token, parts: Vec<Expr>) -> Self {
+        Self {
+            token: Box::new(token),
+            parts,
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn parts(&self) -> &[Expr] {
+        &self.parts
+    }
+}
+
+impl Display for InterpolationExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(interpolate")?;
+        for part in &self.parts {
+            write!(f, " {}", part)?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub struct ListExpr {
+    bracket: Box<Token>,
+    elements: Vec<Expr>,
+}
+
+impl ListExpr {
+    pub fn new(bracket: Token, elements: Vec<Expr>) -> Self {
+        Self {
+            bracket: Box::new(bracket),
+            elements,
+        }
+    }
+
+    pub fn bracket(&self) -> &Token {
+        &self.bracket
+    }
+
+    pub fn elements(&self) -> &[Expr] {
+        &self.elements
+    }
+}
+
+impl Display for ListExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(list")?;
+        for element in &self.elements {
+            write!(f, " {}", element)?;
+        }
+        write!(f, ")")
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum LiteralExpr {
     Number(f64),
-    String(String),
+    String(Rc<str>),
     Boolean(bool),
     Nil(),
 }
@@ -95,6 +288,129 @@ impl Display for LiteralExpr {
     }
 }
 
+pub struct SetExpr {
+    object: Box<Expr>,
+    name: Box<Token>,
+    value: Box<Expr>,
+}
+
+impl SetExpr {
+    pub fn new(object: Expr, name: Token, value: Expr) -> Self {
+        Self {
+            object: Box::new(object),
+            name: Box::new(name),
+            value: Box::new(value),
+        }
+    }
+
+    pub fn object(&self) -> &Expr {
+        &self.object
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+}
+
+impl Display for SetExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "(set {} {} {})",
+            self.object,
+            property_name(&self.name),
+            self.value
+        )
+    }
+}
+
+pub struct MapExpr {
+    brace: Box<Token>,
+    entries: Vec<(Expr, Expr)>,
+}
+
+impl MapExpr {
+    pub fn new(brace: Token, entries: Vec<(Expr, Expr)>) -> Self {
+        Self {
+            brace: Box::new(brace),
+            entries,
+        }
+    }
+
+    pub fn brace(&self) -> &Token {
+        &self.brace
+    }
+
+    pub fn entries(&self) -> &[(Expr, Expr)] {
+        &self.entries
+    }
+}
+
+impl Display for MapExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(map")?;
+        for (key, value) in &self.entries {
+            write!(f, " ({} {})", key, value)?;
+        }
+        write!(f, ")")
+    }
+}
+
+pub struct SetIndexExpr {
+    object: Box<Expr>,
+    bracket: Box<Token>,
+    index: Box<Expr>,
+    value: Box<Expr>,
+}
+
+impl SetIndexExpr {
+    pub fn new(object: Expr, bracket: Token, index: Expr, value: Expr) -> Self {
+        Self {
+            object: Box::new(object),
+            bracket: Box::new(bracket),
+            index: Box::new(index),
+            value: Box::new(value),
+        }
+    }
+
+    pub fn object(&self) -> &Expr {
+        &self.object
+    }
+
+    pub fn bracket(&self) -> &Token {
+        &self.bracket
+    }
+
+    pub fn index(&self) -> &Expr {
+        &self.index
+    }
+
+    pub fn value(&self) -> &Expr {
+        &self.value
+    }
+}
+
+impl Display for SetIndexExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "(set-index {} {} {})",
+            self.object, self.index, self.value
+        )
+    }
+}
+
+fn property_name(name: &Token) -> String {
+    match name.token_type() {
+        TokenType::Identifier(name) => name.to_string(),
+        _ => name.to_string(),
+    }
+}
+
 pub struct UnaryExpr {
     op: Box<Token>,
     expr: Box<Expr>,
@@ -119,6 +435,31 @@ impl UnaryExpr {
 
 impl Display for UnaryExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.op, self.expr)
+        write!(f, "({} {})", self.op.token_type(), self.expr)
+    }
+}
+
+pub struct VariableExpr {
+    name: Box<Token>,
+}
+
+impl VariableExpr {
+    pub fn new(name: Token) -> Self {
+        Self {
+            name: Box::new(name),
+        }
+    }
+
+    pub fn name(&self) -> &Token {
+        &self.name
+    }
+}
+
+impl Display for VariableExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name.token_type() {
+            TokenType::Identifier(name) => name.fmt(f),
+            _ => self.name.fmt(f),
+        }
     }
 }
@@ -0,0 +1,50 @@
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::{StepLimitExceeded, Timeout};
+
+// Reading the clock on every step would dominate tight loops, so the deadline
+// is only checked once per this many steps.
+const CLOCK_CHECK_INTERVAL: u64 = 256;
+
+pub struct Budget {
+    max_steps: Option<u64>,
+    timeout: Option<Duration>,
+    steps: u64,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    pub fn new(config: &Config) -> Budget {
+        Budget {
+            max_steps: config.max_steps,
+            timeout: config.timeout,
+            steps: 0,
+            deadline: None,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.steps = 0;
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    pub fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+
+        if let Some(max_steps) = self.max_steps {
+            if self.steps > max_steps {
+                return Err(StepLimitExceeded(max_steps));
+            }
+        }
+
+        if let (Some(deadline), Some(timeout)) = (self.deadline, self.timeout) {
+            if self.steps.is_multiple_of(CLOCK_CHECK_INTERVAL) && Instant::now() >= deadline {
+                return Err(Timeout(timeout));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Display;
+
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::PermissionDenied;
+
+// No builtin touches the filesystem, the environment or the process yet, so
+// FsRead, FsWrite, Env and Exit are inert: granting or denying them changes
+// nothing until natives that need them check for them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Capability {
+    FsRead,
+    FsWrite,
+    Env,
+    Clock,
+    Exit,
+    Stdin,
+    Stdout,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 7] = [
+        Capability::FsRead,
+        Capability::FsWrite,
+        Capability::Env,
+        Capability::Clock,
+        Capability::Exit,
+        Capability::Stdin,
+        Capability::Stdout,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::FsRead => "fs-read",
+            Capability::FsWrite => "fs-write",
+            Capability::Env => "env",
+            Capability::Clock => "clock",
+            Capability::Exit => "exit",
+            Capability::Stdin => "stdin",
+            Capability::Stdout => "stdout",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Capability> {
+        Capability::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// The set of host resources a script is allowed to touch. Natives call
+// `check` before using a resource.
+//
+// By default only stdout is granted, since output already goes to a sink the
+// host chose. Scripts can't read the clock or stdin unless the host grants
+// those too, or starts from `Capabilities::all()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    granted: u8,
+}
+
+impl Capabilities {
+    pub fn all() -> Capabilities {
+        Capabilities {
+            granted: Capability::ALL.iter().fold(0, |bits, c| bits | c.bit()),
+        }
+    }
+
+    pub fn none() -> Capabilities {
+        Capabilities { granted: 0 }
+    }
+
+    pub fn grant(&mut self, capability: Capability) {
+        self.granted |= capability.bit();
+    }
+
+    pub fn revoke(&mut self, capability: Capability) {
+        self.granted &= !capability.bit();
+    }
+
+    pub fn has(&self, capability: Capability) -> bool {
+        self.granted & capability.bit() != 0
+    }
+
+    pub fn check(&self, capability: Capability) -> Result<(), RuntimeError> {
+        if self.has(capability) {
+            Ok(())
+        } else {
+            Err(PermissionDenied(capability))
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Capabilities {
+        let mut capabilities = Capabilities::none();
+        capabilities.grant(Capability::Stdout);
+        capabilities
+    }
+}
@@ -0,0 +1,213 @@
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::InstanceInUse;
+use crate::symbol::Symbol;
+use crate::value::Value;
+
+type Constructor = dyn Fn(&[Value]) -> Result<Box<dyn Any>, RuntimeError>;
+type Method = dyn Fn(&mut dyn Any, &[Value]) -> Result<Value, RuntimeError>;
+type Getter = dyn Fn(&dyn Any) -> Result<Value, RuntimeError>;
+type Setter = dyn Fn(&mut dyn Any, Value) -> Result<(), RuntimeError>;
+
+// A class implemented by the host. Its instances wrap an opaque Rust value that
+// is dropped along with the last reference to the instance.
+pub struct HostClass {
+    name: Symbol,
+    arity: usize,
+    constructor: Box<Constructor>,
+    methods: HashMap<Symbol, Rc<HostMethod>>,
+    getters: HashMap<Symbol, Box<Getter>>,
+    setters: HashMap<Symbol, Box<Setter>>,
+}
+
+impl HostClass {
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn construct(self: &Rc<Self>, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        let data = (self.constructor)(arguments)?;
+        Ok(Value::Instance(Rc::new(Instance {
+            class: Rc::clone(self),
+            data: RefCell::new(data),
+        })))
+    }
+}
+
+pub struct HostMethod {
+    name: Symbol,
+    arity: usize,
+    function: Box<Method>,
+}
+
+pub struct Instance {
+    class: Rc<HostClass>,
+    data: RefCell<Box<dyn Any>>,
+}
+
+impl Instance {
+    pub fn class(&self) -> &Rc<HostClass> {
+        &self.class
+    }
+
+    // Returns `None` if the data is of another type, or is currently borrowed
+    // by one of the instance's own methods.
+    pub fn data<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        let data = self.data.try_borrow().ok()?;
+        Ref::filter_map(data, |data| data.downcast_ref()).ok()
+    }
+
+    // The size of the data itself, not counting anything it owns. Data that
+    // is borrowed by one of the instance's own methods counts as empty.
+    pub fn size(&self) -> usize {
+        self.data
+            .try_borrow()
+            .map_or(0, |data| mem::size_of_val(&**data))
+    }
+
+    // Getters take precedence over methods of the same name.
+    pub fn get(self: &Rc<Self>, name: Symbol) -> Option<Result<Value, RuntimeError>> {
+        if let Some(getter) = self.class.getters.get(&name) {
+            return Some(self.borrow_data().and_then(|data| getter(data.as_ref())));
+        }
+
+        self.class.methods.get(&name).map(|method| {
+            Ok(Value::BoundMethod(Rc::new(BoundMethod {
+                receiver: Rc::clone(self),
+                method: Rc::clone(method),
+            })))
+        })
+    }
+
+    pub fn set(&self, name: Symbol, value: Value) -> Option<Result<(), RuntimeError>> {
+        self.class
+            .setters
+            .get(&name)
+            .map(|setter| setter(self.borrow_data_mut()?.as_mut(), value))
+    }
+
+    // Host code can call back into Lox while one of the instance's methods is
+    // running, e.g. `c.each(c.poke)`, which must not borrow the data again.
+    fn borrow_data(&self) -> Result<Ref<'_, Box<dyn Any>>, RuntimeError> {
+        self.data
+            .try_borrow()
+            .map_err(|_| InstanceInUse(self.class.name))
+    }
+
+    fn borrow_data_mut(&self) -> Result<RefMut<'_, Box<dyn Any>>, RuntimeError> {
+        self.data
+            .try_borrow_mut()
+            .map_err(|_| InstanceInUse(self.class.name))
+    }
+}
+
+pub struct BoundMethod {
+    receiver: Rc<Instance>,
+    method: Rc<HostMethod>,
+}
+
+impl BoundMethod {
+    pub fn name(&self) -> Symbol {
+        self.method.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.method.arity
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        let mut data = self.receiver.borrow_data_mut()?;
+        (self.method.function)(data.as_mut(), arguments)
+    }
+}
+
+// Describes a host class backed by values of type `T`, e.g.
+// `ClassBuilder::new("Connection", 1, |args| ...).method("query", 1, |conn, args| ...)`.
+pub struct ClassBuilder<T> {
+    class: HostClass,
+    marker: PhantomData<T>,
+}
+
+impl<T: 'static> ClassBuilder<T> {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        constructor: impl Fn(&[Value]) -> Result<T, RuntimeError> + 'static,
+    ) -> Self {
+        Self {
+            class: HostClass {
+                name: Symbol::intern(name),
+                arity,
+                constructor: Box::new(move |arguments| {
+                    constructor(arguments).map(|data| Box::new(data) as Box<dyn Any>)
+                }),
+                methods: HashMap::new(),
+                getters: HashMap::new(),
+                setters: HashMap::new(),
+            },
+            marker: PhantomData,
+        }
+    }
+
+    pub fn method(
+        mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&mut T, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    ) -> Self {
+        let name = Symbol::intern(name);
+        let method = HostMethod {
+            name,
+            arity,
+            function: Box::new(move |data, arguments| function(downcast_mut(data), arguments)),
+        };
+        self.class.methods.insert(name, Rc::new(method));
+        self
+    }
+
+    pub fn getter(
+        mut self,
+        name: &str,
+        function: impl Fn(&T) -> Result<Value, RuntimeError> + 'static,
+    ) -> Self {
+        let getter = Box::new(move |data: &dyn Any| function(downcast_ref(data)));
+        self.class.getters.insert(Symbol::intern(name), getter);
+        self
+    }
+
+    pub fn setter(
+        mut self,
+        name: &str,
+        function: impl Fn(&mut T, Value) -> Result<(), RuntimeError> + 'static,
+    ) -> Self {
+        let setter = Box::new(move |data: &mut dyn Any, value| function(downcast_mut(data), value));
+        self.class.setters.insert(Symbol::intern(name), setter);
+        self
+    }
+
+    pub fn build(self) -> HostClass {
+        self.class
+    }
+}
+
+// Only closures registered through a `ClassBuilder<T>` ever see the data, and
+// they are only attached to instances constructed as `T`.
+fn downcast_ref<T: 'static>(data: &dyn Any) -> &T {
+    data.downcast_ref()
+        .expect("Error: Host data has the wrong type")
+}
+
+fn downcast_mut<T: 'static>(data: &mut dyn Any) -> &mut T {
+    data.downcast_mut()
+        .expect("Error: Host data has the wrong type")
+}
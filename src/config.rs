@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crate::capabilities::Capabilities;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    TreeWalker,
+    Vm,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub backend: Backend,
+    pub optimize: bool,
+    pub dump_ast: bool,
+    pub trace: bool,
+    pub gc_stress: bool,
+    pub gc_stats: bool,
+    pub max_heap: Option<usize>,
+    pub max_parse_depth: usize,
+    pub max_call_depth: usize,
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+    pub capabilities: Capabilities,
+    pub prelude: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            backend: Backend::default(),
+            optimize: false,
+            dump_ast: false,
+            trace: false,
+            gc_stress: false,
+            gc_stats: false,
+            max_heap: None,
+            // Deep enough for real scripts, yet shallow enough that a debug build
+            // stays within the 2 MB stack that spawned threads get by default.
+            // Raise them only when running on a bigger stack.
+            max_parse_depth: 128,
+            max_call_depth: 128,
+            max_steps: None,
+            timeout: None,
+            capabilities: Capabilities::default(),
+            prelude: true,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::{InvalidConversion, TypeMismatch};
+use crate::list;
+use crate::map;
+use crate::value::Value;
+
+pub trait IntoLox {
+    fn into_lox(self) -> Value;
+}
+
+pub trait FromLox: Sized {
+    fn from_lox(value: &Value) -> Result<Self, RuntimeError>;
+}
+
+impl IntoLox for Value {
+    fn into_lox(self) -> Value {
+        self
+    }
+}
+
+impl FromLox for Value {
+    fn from_lox(value: &Value) -> Result<Value, RuntimeError> {
+        Ok(value.clone())
+    }
+}
+
+impl IntoLox for () {
+    fn into_lox(self) -> Value {
+        Value::Nil
+    }
+}
+
+impl FromLox for () {
+    fn from_lox(value: &Value) -> Result<(), RuntimeError> {
+        match value {
+            Value::Nil => Ok(()),
+            _ => Err(TypeMismatch("nil", value.type_name())),
+        }
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(value: &Value) -> Result<bool, RuntimeError> {
+        match value {
+            Value::Boolean(b) => Ok(*b),
+            _ => Err(TypeMismatch("boolean", value.type_name())),
+        }
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromLox for f64 {
+    fn from_lox(value: &Value) -> Result<f64, RuntimeError> {
+        match value {
+            Value::Number(n) => Ok(*n),
+            _ => Err(TypeMismatch("number", value.type_name())),
+        }
+    }
+}
+
+impl IntoLox for f32 {
+    fn into_lox(self) -> Value {
+        Value::Number(self as f64)
+    }
+}
+
+impl FromLox for f32 {
+    fn from_lox(value: &Value) -> Result<f32, RuntimeError> {
+        f64::from_lox(value).map(|n| n as f32)
+    }
+}
+
+// Lox only has doubles, so converting back to an integer fails unless the
+// number is whole and fits in the target type.
+macro_rules! integer_conversions {
+    ($($int:ty),*) => {
+        $(
+            impl IntoLox for $int {
+                fn into_lox(self) -> Value {
+                    Value::Number(self as f64)
+                }
+            }
+
+            impl FromLox for $int {
+                fn from_lox(value: &Value) -> Result<$int, RuntimeError> {
+                    let n = f64::from_lox(value)?;
+                    // The largest 64-bit values round up to the next power of
+                    // two as doubles, so the upper bound is that power of two,
+                    // which is exact, and is itself out of range.
+                    let signed = (<$int>::MIN != 0) as i32;
+                    let end = 2f64.powi(<$int>::BITS as i32 - signed);
+                    if n.fract() == 0.0 && n >= <$int>::MIN as f64 && n < end {
+                        Ok(n as $int)
+                    } else {
+                        Err(InvalidConversion(n, stringify!($int)))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl IntoLox for &str {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(value: &Value) -> Result<String, RuntimeError> {
+        match value {
+            Value::String(s) => Ok(s.to_string()),
+            _ => Err(TypeMismatch("string", value.type_name())),
+        }
+    }
+}
+
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> Value {
+        match self {
+            Some(value) => value.into_lox(),
+            None => Value::Nil,
+        }
+    }
+}
+
+impl<T: FromLox> FromLox for Option<T> {
+    fn from_lox(value: &Value) -> Result<Option<T>, RuntimeError> {
+        match value {
+            Value::Nil => Ok(None),
+            _ => T::from_lox(value).map(Some),
+        }
+    }
+}
+
+// Lists convert by copying their items, so changes on one side aren't seen on
+// the other.
+impl<T: IntoLox> IntoLox for Vec<T> {
+    fn into_lox(self) -> Value {
+        list::new(self.into_iter().map(IntoLox::into_lox).collect())
+    }
+}
+
+impl<T: FromLox> FromLox for Vec<T> {
+    fn from_lox(value: &Value) -> Result<Vec<T>, RuntimeError> {
+        match value {
+            Value::List(items) => items.borrow().iter().map(T::from_lox).collect(),
+            _ => Err(TypeMismatch("list", value.type_name())),
+        }
+    }
+}
+
+// Maps convert by copying too. Only string keys are supported, since those are
+// the keys that every Lox map can hold.
+impl<V: IntoLox> IntoLox for HashMap<String, V> {
+    fn into_lox(self) -> Value {
+        let entries = self
+            .into_iter()
+            .map(|(key, value)| (key.into_lox(), value.into_lox()))
+            .collect();
+        map::new(entries).expect("string keys are hashable")
+    }
+}
+
+impl<V: FromLox> FromLox for HashMap<String, V> {
+    fn from_lox(value: &Value) -> Result<HashMap<String, V>, RuntimeError> {
+        match value {
+            Value::Map(entries) => entries
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((String::from_lox(&key.value())?, V::from_lox(value)?)))
+                .collect(),
+            _ => Err(TypeMismatch("map", value.type_name())),
+        }
+    }
+}
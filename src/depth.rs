@@ -0,0 +1,35 @@
+use std::cell::Cell;
+
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::StackOverflow;
+
+// Builtins like `map` call back into values straight from Rust, so calls can
+// recurse without passing through the interpreter or the VM. How deeply
+// evaluation and calls are nested is therefore kept per thread, and checked
+// against the limit of the run in progress.
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static MAX_DEPTH: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+// Runs `f` with nesting limited to `max_depth` levels.
+pub fn limit<T>(max_depth: usize, f: impl FnOnce() -> T) -> T {
+    let outer = MAX_DEPTH.replace(max_depth);
+    let result = f();
+    MAX_DEPTH.set(outer);
+    result
+}
+
+// Runs `f` one level deeper, or fails with a stack overflow once the limit is
+// reached.
+pub fn nested<T>(f: impl FnOnce() -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+    let depth = DEPTH.get();
+    if depth >= MAX_DEPTH.get() {
+        return Err(StackOverflow());
+    }
+
+    DEPTH.set(depth + 1);
+    let result = f();
+    DEPTH.set(depth);
+    result
+}
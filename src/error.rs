@@ -0,0 +1,49 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use crate::interpreter::RuntimeError;
+use crate::parser::ParseError;
+use crate::vm::compiler::CompileError;
+
+#[derive(Debug)]
+pub enum Error {
+    Scan(String),
+    Parse(ParseError),
+    Compile(CompileError),
+    Runtime(RuntimeError),
+    Read(String, io::Error),
+    Write(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Scan(msg) => write!(f, "{}", msg),
+            Error::Parse(error) => error.fmt(f),
+            Error::Compile(error) => error.fmt(f),
+            Error::Runtime(error) => error.fmt(f),
+            Error::Read(path, error) => write!(f, "Could not read file '{}': {}", path, error),
+            Error::Write(error) => write!(f, "Error writing output: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Error {
+        Error::Parse(error)
+    }
+}
+
+impl From<CompileError> for Error {
+    fn from(error: CompileError) -> Error {
+        Error::Compile(error)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Error {
+        Error::Runtime(error)
+    }
+}
@@ -1,127 +1,360 @@
+use crate::ast::Expr;
 use crate::ast::Expr::Literal;
-use crate::ast::{Expr, LiteralExpr};
+use crate::budget::Budget;
+use crate::capabilities::Capability;
+use crate::config::Config;
+use crate::depth;
 use crate::interpreter::RuntimeError::{
-    ExpectedNumberLiterals, InvalidLiteralForUnary, InvalidOperandError, UnknownOperatorError,
+    AssertionFailed, ExpectedNumberLiterals, IndexOutOfRange, InputError, InstanceInUse,
+    InvalidConversion, InvalidIndex, InvalidLiteralForUnary, InvalidNumber, InvalidOperandError,
+    NotAnInstance, NotAssignable, NotCallable, NotIndexable, OutOfMemory, Panic, PermissionDenied,
+    StackOverflow, StepLimitExceeded, Timeout, Traced, TypeMismatch, UndefinedGlobal,
+    UndefinedMethod, UndefinedProperty, UndefinedVariable, UnhashableKey, UnknownOperatorError,
+    WrongArgumentCount, WrongArity,
 };
+use crate::list;
+use crate::map;
+use crate::symbol::Symbol;
 use crate::token::{Token, TokenType};
+use crate::value::Value;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::io;
+use std::time::Duration;
 
+#[derive(Debug)]
 pub enum RuntimeError {
     ExpectedNumberLiterals(String),
     UnknownOperatorError(Token),
     InvalidOperandError(Token),
     InvalidLiteralForUnary(Token),
+    OutOfMemory(usize),
+    StackOverflow(),
+    StepLimitExceeded(u64),
+    Timeout(Duration),
+    PermissionDenied(Capability),
+    UndefinedVariable(Token),
+    NotCallable(Token),
+    WrongArity(Token, usize, usize),
+    TypeMismatch(&'static str, &'static str),
+    InvalidConversion(f64, &'static str),
+    UndefinedGlobal(String),
+    UndefinedMethod(&'static str, String),
+    WrongArgumentCount(Symbol, usize, usize),
+    UndefinedProperty(Token),
+    NotAnInstance(Token),
+    InvalidNumber(String),
+    AssertionFailed(String),
+    Panic(String),
+    IndexOutOfRange(f64, usize),
+    InvalidIndex(f64),
+    NotIndexable(Token),
+    NotAssignable(Token),
+    UnhashableKey(&'static str),
+    InstanceInUse(Symbol),
+    InputError(io::Error),
+    Traced(Box<RuntimeError>, Vec<Frame>),
 }
 
-impl Display for RuntimeError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl RuntimeError {
+    // The error itself, without the calls it passed through.
+    pub fn cause(&self) -> &RuntimeError {
         match self {
-            ExpectedNumberLiterals(msg) => write!(f, "{}", msg),
-            UnknownOperatorError(token) => write!(f, "Unknown operator: {}", token),
-            InvalidOperandError(token) => write!(f, "Invalid operand: {}", token),
-            InvalidLiteralForUnary(token) => write!(f, "Invalid literal for unary: {}", token),
+            Traced(cause, _) => cause,
+            error => error,
+        }
+    }
+
+    // The calls that were in progress when the error happened, innermost first.
+    pub fn trace(&self) -> &[Frame] {
+        match self {
+            Traced(_, frames) => frames,
+            _ => &[],
         }
     }
+
+    pub(crate) fn in_call(self, name: Symbol, line: Option<usize>) -> RuntimeError {
+        let frame = Frame { name, line };
+        match self {
+            Traced(cause, mut frames) => {
+                frames.push(frame);
+                Traced(cause, frames)
+            }
+            error => Traced(Box::new(error), vec![frame]),
+        }
+    }
+}
+
+// A call that was in progress when an error happened. Calls made by the host
+// have no line.
+#[derive(Debug)]
+pub struct Frame {
+    name: Symbol,
+    line: Option<usize>,
 }
 
-fn is_truthy(literal: &LiteralExpr) -> bool {
-    match literal {
-        LiteralExpr::Nil() => false,
-        LiteralExpr::Boolean(b) => *b,
-        _ => true,
+impl Frame {
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
     }
 }
 
-fn is_equal(l1: &LiteralExpr, l2: &LiteralExpr) -> bool {
-    match (l1, l2) {
-        (LiteralExpr::Nil(), LiteralExpr::Nil()) => true,
-        (LiteralExpr::Boolean(b1), LiteralExpr::Boolean(b2)) => b1 == b2,
-        (LiteralExpr::Number(n1), LiteralExpr::Number(n2)) => (n1 - n2).abs() < 0.01,
-        (LiteralExpr::String(s1), LiteralExpr::String(s2)) => s1 == s2,
-        _ => false,
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "[line {}] in {}()", line, self.name),
+            None => write!(f, "in {}()", self.name),
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpectedNumberLiterals(msg) => write!(f, "{}", msg),
+            UnknownOperatorError(token) => write!(f, "Unknown operator: {}", token),
+            InvalidOperandError(token) => write!(f, "Invalid operand: {}", token),
+            InvalidLiteralForUnary(token) => write!(f, "Invalid literal for unary: {}", token),
+            OutOfMemory(max_heap) => {
+                write!(
+                    f,
+                    "Out of memory: heap limit of {} bytes exceeded",
+                    max_heap
+                )
+            }
+            StackOverflow() => write!(f, "Stack overflow"),
+            StepLimitExceeded(max_steps) => {
+                write!(f, "Execution budget of {} steps exceeded", max_steps)
+            }
+            Timeout(timeout) => write!(f, "Execution timed out after {:?}", timeout),
+            PermissionDenied(capability) => {
+                write!(f, "Permission denied: missing '{}' capability", capability)
+            }
+            UndefinedVariable(token) => write!(f, "Undefined variable: {}", token),
+            NotCallable(token) => write!(f, "Can only call functions: {}", token),
+            WrongArity(token, expected, got) => write!(
+                f,
+                "Expected {} arguments but got {}: {}",
+                expected, got, token
+            ),
+            TypeMismatch(expected, found) => write!(f, "Expected {} but got {}", expected, found),
+            InvalidConversion(n, target) => write!(f, "Cannot convert {} to {}", n, target),
+            UndefinedProperty(token) => write!(f, "Undefined property: {}", token),
+            NotAnInstance(token) => write!(f, "Only instances have properties: {}", token),
+            InvalidNumber(s) => write!(f, "Could not parse '{}' as a number", s),
+            AssertionFailed(msg) => write!(f, "Assertion failed: {}", msg),
+            Panic(msg) => write!(f, "Panic: {}", msg),
+            IndexOutOfRange(index, len) => {
+                write!(f, "Index {} is out of range for length {}", index, len)
+            }
+            InvalidIndex(index) => write!(f, "Index must be an integer but got {}", index),
+            NotIndexable(token) => write!(f, "Can only index lists, maps and strings: {}", token),
+            NotAssignable(token) => {
+                write!(f, "Can only assign to list and map elements: {}", token)
+            }
+            UnhashableKey(type_name) => write!(f, "Can't use {} as a map key", type_name),
+            InputError(error) => write!(f, "Error reading user input: {}", error),
+            Traced(cause, frames) => {
+                write!(f, "{}", cause)?;
+                for frame in frames {
+                    write!(f, "\n{}", frame)?;
+                }
+                Ok(())
+            }
+            InstanceInUse(class) => write!(
+                f,
+                "Can't use a {} instance while one of its methods is running",
+                class
+            ),
+            UndefinedGlobal(name) => write!(f, "Undefined variable: {}", name),
+            UndefinedMethod(type_name, name) => {
+                write!(f, "Undefined method '{}' on {}", name, type_name)
+            }
+            WrongArgumentCount(name, expected, got) => write!(
+                f,
+                "Expected {} arguments but got {}: {}",
+                expected, got, name
+            ),
+        }
     }
 }
 
 fn check_number_operands(
     operator: &str,
-    left: &LiteralExpr,
-    right: &LiteralExpr,
-    func: fn(f64, f64) -> LiteralExpr,
-) -> Result<LiteralExpr, RuntimeError> {
-    if let (LiteralExpr::Number(n1), LiteralExpr::Number(n2)) = (left, right) {
+    left: &Value,
+    right: &Value,
+    func: fn(f64, f64) -> Value,
+) -> Result<Value, RuntimeError> {
+    if let (Value::Number(n1), Value::Number(n2)) = (left, right) {
         Ok(func(*n1, *n2))
     } else {
         Err(ExpectedNumberLiterals(operator.to_string()))
     }
 }
 
-pub fn interpret(expression: &Expr) -> Result<(), RuntimeError> {
-    match interpret_expr(expression) {
-        Ok(literal_expr) => {
-            println!("{}", literal_expr);
-            Ok(())
+pub struct Interpreter {
+    max_depth: usize,
+    budget: Budget,
+    globals: HashMap<Symbol, Value>,
+}
+
+impl Interpreter {
+    pub fn new(config: &Config) -> Interpreter {
+        Interpreter {
+            max_depth: config.max_call_depth,
+            budget: Budget::new(config),
+            globals: HashMap::new(),
         }
-        Err(err) => Err(err),
     }
-}
 
-fn interpret_expr(expr: &Expr) -> Result<LiteralExpr, RuntimeError> {
-    match expr {
-        Expr::Binary(expr) => {
-            let left = interpret_expr(expr.left())?;
-            let right = interpret_expr(expr.right())?;
+    pub fn define_global(&mut self, name: Symbol, value: Value) {
+        self.globals.insert(name, value);
+    }
+
+    pub fn global(&self, name: Symbol) -> Option<Value> {
+        self.globals.get(&name).cloned()
+    }
+
+    pub fn evaluate(&mut self, expression: &Expr) -> Result<Value, RuntimeError> {
+        self.budget.start();
+        depth::limit(self.max_depth, || self.interpret_expr(expression))
+    }
+
+    fn interpret_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.budget.step()?;
+        depth::nested(|| self.interpret_node(expr))
+    }
 
-            match expr.op().token_type() {
-                TokenType::Plus => match (left, right) {
-                    (LiteralExpr::Number(n1), LiteralExpr::Number(n2)) => {
-                        Ok(LiteralExpr::Number(n1 + n2))
+    fn interpret_node(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        match expr {
+            Expr::Binary(expr) => {
+                let left = self.interpret_expr(expr.left())?;
+                let right = self.interpret_expr(expr.right())?;
+
+                match expr.op().token_type() {
+                    TokenType::Plus => match (left, right) {
+                        (Value::Number(n1), Value::Number(n2)) => Ok(Value::Number(n1 + n2)),
+                        (Value::String(s1), Value::String(s2)) => {
+                            let string = format!("{}{}", s1, s2);
+                            Ok(Value::String(string.into()))
+                        }
+                        _ => Err(InvalidOperandError(expr.op().clone())),
+                    },
+                    TokenType::Minus => {
+                        check_number_operands("-", &left, &right, |n1, n2| Value::Number(n1 - n2))
                     }
-                    (LiteralExpr::String(s1), LiteralExpr::String(s2)) => {
-                        Ok(LiteralExpr::String(format!("{}{}", s1, s2)))
+                    TokenType::Star => {
+                        check_number_operands("*", &left, &right, |n1, n2| Value::Number(n1 * n2))
                     }
-                    _ => Err(InvalidOperandError(expr.op().clone())),
-                },
-                TokenType::Minus => {
-                    check_number_operands("-", &left, &right, |n1, n2| LiteralExpr::Number(n1 - n2))
+                    TokenType::Slash => {
+                        check_number_operands("/", &left, &right, |n1, n2| Value::Number(n1 / n2))
+                    }
+                    TokenType::Greater => {
+                        check_number_operands(">", &left, &right, |n1, n2| Value::Boolean(n1 > n2))
+                    }
+                    TokenType::GreaterEqual => {
+                        check_number_operands(">=", &left, &right, |n1, n2| {
+                            Value::Boolean(n1 >= n2)
+                        })
+                    }
+                    TokenType::Less => {
+                        check_number_operands("<", &left, &right, |n1, n2| Value::Boolean(n1 < n2))
+                    }
+                    TokenType::LessEqual => check_number_operands("<=", &left, &right, |n1, n2| {
+                        Value::Boolean(n1 <= n2)
+                    }),
+                    TokenType::EqualEqual => Ok(Value::Boolean(left.is_equal(&right))),
+                    TokenType::BangEqual => Ok(Value::Boolean(!left.is_equal(&right))),
+                    _ => Err(UnknownOperatorError(expr.op().clone())),
                 }
-                TokenType::Star => {
-                    check_number_operands("*", &left, &right, |n1, n2| LiteralExpr::Number(n1 * n2))
+            }
+            Expr::Call(c) => {
+                let callee = self.interpret_expr(c.callee())?;
+
+                let mut arguments = Vec::with_capacity(c.arguments().len());
+                for argument in c.arguments() {
+                    arguments.push(self.interpret_expr(argument)?);
                 }
-                TokenType::Slash => {
-                    check_number_operands("/", &left, &right, |n1, n2| LiteralExpr::Number(n1 / n2))
+
+                match callee.arity().map(|arity| arity.expected(arguments.len())) {
+                    Some(Some(expected)) => {
+                        Err(WrongArity(c.paren().clone(), expected, arguments.len()))
+                    }
+                    Some(None) => callee.call(&arguments).map_err(|error| {
+                        error.in_call(callee.callee_name(), Some(c.paren().span().line()))
+                    }),
+                    None => Err(NotCallable(c.paren().clone())),
                 }
-                TokenType::Greater => check_number_operands(">", &left, &right, |n1, n2| {
-                    LiteralExpr::Boolean(n1 > n2)
-                }),
-                TokenType::GreaterEqual => check_number_operands(">=", &left, &right, |n1, n2| {
-                    LiteralExpr::Boolean(n1 >= n2)
-                }),
-                TokenType::Less => check_number_operands("<", &left, &right, |n1, n2| {
-                    LiteralExpr::Boolean(n1 < n2)
-                }),
-                TokenType::LessEqual => check_number_operands("<=", &left, &right, |n1, n2| {
-                    LiteralExpr::Boolean(n1 <= n2)
-                }),
-                TokenType::EqualEqual => Ok(LiteralExpr::Boolean(is_equal(&left, &right))),
-                TokenType::BangEqual => Ok(LiteralExpr::Boolean(!is_equal(&left, &right))),
-                _ => Err(UnknownOperatorError(expr.op().clone())),
             }
-        }
-        Expr::Grouping(e) => interpret_expr(e.expression()),
-        Literal(l) => Ok(l.clone()),
-        Expr::Unary(u) => {
-            let right = interpret_expr(u.expr())?;
-            match u.op().token_type() {
-                TokenType::Minus => {
-                    if let LiteralExpr::Number(n) = right {
-                        Ok(LiteralExpr::Number(-n))
-                    } else {
-                        Err(InvalidLiteralForUnary(u.op().clone()))
+            Expr::Get(g) => self.interpret_expr(g.object())?.get_property(g.name()),
+            Expr::Grouping(e) => self.interpret_expr(e.expression()),
+            Expr::Index(i) => {
+                let object = self.interpret_expr(i.object())?;
+                let index = self.interpret_expr(i.index())?;
+                object.index(&index, i.bracket())
+            }
+            Expr::Interpolation(i) => {
+                let mut string = String::new();
+                for part in i.parts() {
+                    string.push_str(&self.interpret_expr(part)?.to_string());
+                }
+                Ok(Value::String(string.into()))
+            }
+            Expr::List(l) => {
+                let mut elements = Vec::with_capacity(l.elements().len());
+                for element in l.elements() {
+                    elements.push(self.interpret_expr(element)?);
+                }
+                Ok(list::new(elements))
+            }
+            Literal(l) => Ok(Value::from(l)),
+            Expr::Map(m) => {
+                let mut entries = Vec::with_capacity(m.entries().len());
+                for (key, value) in m.entries() {
+                    let key = self.interpret_expr(key)?;
+                    let value = self.interpret_expr(value)?;
+                    entries.push((key, value));
+                }
+                map::new(entries)
+            }
+            Expr::Set(s) => {
+                let object = self.interpret_expr(s.object())?;
+                let value = self.interpret_expr(s.value())?;
+                object.set_property(s.name(), value.clone())?;
+                Ok(value)
+            }
+            Expr::SetIndex(s) => {
+                let object = self.interpret_expr(s.object())?;
+                let index = self.interpret_expr(s.index())?;
+                let value = self.interpret_expr(s.value())?;
+                object.set_index(&index, value.clone(), s.bracket())?;
+                Ok(value)
+            }
+            Expr::Unary(u) => {
+                let right = self.interpret_expr(u.expr())?;
+                match u.op().token_type() {
+                    TokenType::Minus => {
+                        if let Value::Number(n) = right {
+                            Ok(Value::Number(-n))
+                        } else {
+                            Err(InvalidLiteralForUnary(u.op().clone()))
+                        }
                     }
+                    TokenType::Bang => Ok(Value::Boolean(!right.is_truthy())),
+                    _ => Err(UnknownOperatorError(u.op().clone())),
                 }
-                TokenType::Bang => Ok(LiteralExpr::Boolean(!is_truthy(&right))),
-                _ => Err(UnknownOperatorError(u.op().clone())),
             }
+            Expr::Variable(v) => match v.name().token_type() {
+                TokenType::Identifier(name) => match self.globals.get(name) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(UndefinedVariable(v.name().clone())),
+                },
+                _ => Err(UndefinedVariable(v.name().clone())),
+            },
         }
     }
 }
@@ -0,0 +1,36 @@
+mod ast;
+mod budget;
+mod capabilities;
+mod class;
+mod config;
+mod convert;
+mod depth;
+mod error;
+mod interpreter;
+mod list;
+mod lox;
+mod map;
+mod optimizer;
+mod output;
+mod parser;
+mod scanner;
+mod stdlib;
+mod strings;
+mod symbol;
+mod token;
+mod value;
+mod vm;
+
+pub use capabilities::{Capabilities, Capability};
+pub use class::{BoundMethod, ClassBuilder, HostClass, Instance};
+pub use config::Backend;
+pub use convert::{FromLox, IntoLox};
+pub use error::Error;
+pub use interpreter::{Frame, RuntimeError};
+pub use lox::{Lox, LoxBuilder};
+pub use output::SharedBuffer;
+pub use parser::ParseError;
+pub use stdlib::{Builtin, Context, BUILTINS};
+pub use symbol::Symbol;
+pub use value::{Arity, NativeFunction, Value};
+pub use vm::compiler::CompileError;
@@ -0,0 +1,199 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::convert::FromLox;
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::{IndexOutOfRange, InvalidIndex, TypeMismatch};
+use crate::value::{NativeFunction, Value};
+
+pub type List = Rc<RefCell<Vec<Value>>>;
+
+type ListMethod = fn(&List, &[Value]) -> Result<Value, RuntimeError>;
+
+// Name, required arguments, optional arguments and implementation.
+const METHODS: [(&str, usize, usize, ListMethod); 12] = [
+    ("push", 1, 0, push),
+    ("pop", 0, 0, pop),
+    ("insert", 2, 0, insert),
+    ("remove", 1, 0, remove),
+    ("len", 0, 0, len),
+    ("slice", 2, 0, slice),
+    ("contains", 1, 0, contains),
+    ("sort", 0, 1, sort),
+    ("map", 1, 0, map),
+    ("filter", 1, 0, filter),
+    ("reduce", 2, 0, reduce),
+    ("join", 1, 0, join),
+];
+
+pub fn new(items: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(items)))
+}
+
+// Looks up a method on a list, returning it bound to the list.
+pub fn method(receiver: &List, name: &str) -> Option<Value> {
+    let (name, arity, optional, function) = METHODS
+        .into_iter()
+        .find(|(method, _, _, _)| *method == name)?;
+    let receiver = Rc::clone(receiver);
+    let bound = move |arguments: &[Value]| function(&receiver, arguments);
+    let native = NativeFunction::new(name, arity, Box::new(bound)).with_optional(optional);
+    Some(Value::Native(Rc::new(native)))
+}
+
+// Callbacks may themselves touch the list, so the methods that call them work
+// on a copy of its items rather than holding a borrow.
+fn items(list: &List) -> Vec<Value> {
+    list.borrow().clone()
+}
+
+pub fn position(index: &Value, len: usize) -> Result<usize, RuntimeError> {
+    let index = whole(index)?;
+    if index >= 0.0 && index < len as f64 {
+        Ok(index as usize)
+    } else {
+        Err(IndexOutOfRange(index, len))
+    }
+}
+
+// Indices are plain numbers, which only have to be whole.
+fn whole(index: &Value) -> Result<f64, RuntimeError> {
+    let index = f64::from_lox(index)?;
+    if index.fract() == 0.0 {
+        Ok(index)
+    } else {
+        Err(InvalidIndex(index))
+    }
+}
+
+fn push(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    list.borrow_mut().push(arguments[0].clone());
+    Ok(Value::Nil)
+}
+
+fn pop(list: &List, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(list.borrow_mut().pop().unwrap_or(Value::Nil))
+}
+
+fn insert(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mut items = list.borrow_mut();
+    // Inserting at the very end is allowed.
+    let position = position(&arguments[0], items.len() + 1)?;
+    items.insert(position, arguments[1].clone());
+    Ok(Value::Nil)
+}
+
+fn remove(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mut items = list.borrow_mut();
+    let position = position(&arguments[0], items.len())?;
+    Ok(items.remove(position))
+}
+
+fn len(list: &List, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(list.borrow().len() as f64))
+}
+
+fn slice(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let items = list.borrow();
+    let (start, end) = bounds(&arguments[0], &arguments[1], items.len())?;
+    Ok(new(items[start..end].to_vec()))
+}
+
+// Slice bounds past the end are clamped to it.
+pub fn bounds(start: &Value, end: &Value, len: usize) -> Result<(usize, usize), RuntimeError> {
+    let bound = |bound: &Value| match whole(bound)? {
+        bound if bound < 0.0 => Err(IndexOutOfRange(bound, len)),
+        bound => Ok((bound as usize).min(len)),
+    };
+    let end = bound(end)?;
+    let start = bound(start)?.min(end);
+    Ok((start, end))
+}
+
+fn contains(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let found = list
+        .borrow()
+        .iter()
+        .any(|item| item.is_equal(&arguments[0]));
+    Ok(Value::Boolean(found))
+}
+
+// Without a comparator, sorts lists of numbers or lists of strings. A comparator
+// is called with two items and returns a negative number, zero or a positive
+// number to order them.
+fn sort(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mut items = items(list);
+    try_sort(&mut items, |a, b| match arguments.first() {
+        Some(comparator) => ordering(&comparator.call(&[a.clone(), b.clone()])?),
+        None => natural_order(a, b),
+    })?;
+    *list.borrow_mut() = items;
+    Ok(Value::Nil)
+}
+
+// Sorts with a comparison that can fail, stopping at the first error.
+pub fn try_sort<T>(
+    items: &mut [T],
+    mut compare: impl FnMut(&T, &T) -> Result<Ordering, RuntimeError>,
+) -> Result<(), RuntimeError> {
+    let mut error = None;
+    items.sort_by(|a, b| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        compare(a, b).unwrap_or_else(|e| {
+            error = Some(e);
+            Ordering::Equal
+        })
+    });
+    error.map_or(Ok(()), Err)
+}
+
+// The order that the result of a comparator stands for.
+pub fn ordering(result: &Value) -> Result<Ordering, RuntimeError> {
+    let order = f64::from_lox(result)?;
+    Ok(order.partial_cmp(&0.0).unwrap_or(Ordering::Equal))
+}
+
+pub fn natural_order(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+    match (a, b) {
+        (Value::Number(n1), Value::Number(n2)) => Ok(n1.partial_cmp(n2).unwrap_or(Ordering::Equal)),
+        (Value::String(s1), Value::String(s2)) => Ok(s1.cmp(s2)),
+        (Value::Number(_), other) => Err(TypeMismatch("number", other.type_name())),
+        (Value::String(_), other) => Err(TypeMismatch("string", other.type_name())),
+        (other, _) => Err(TypeMismatch("number or string", other.type_name())),
+    }
+}
+
+fn map(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mapped = items(list)
+        .into_iter()
+        .map(|item| arguments[0].call(&[item]))
+        .collect::<Result<Vec<Value>, RuntimeError>>()?;
+    Ok(new(mapped))
+}
+
+fn filter(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mut filtered = Vec::new();
+    for item in items(list) {
+        if arguments[0].call(std::slice::from_ref(&item))?.is_truthy() {
+            filtered.push(item);
+        }
+    }
+    Ok(new(filtered))
+}
+
+fn reduce(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let mut accumulator = arguments[1].clone();
+    for item in items(list) {
+        accumulator = arguments[0].call(&[accumulator, item])?;
+    }
+    Ok(accumulator)
+}
+
+fn join(list: &List, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let separator = String::from_lox(&arguments[0])?;
+    let parts: Vec<String> = list.borrow().iter().map(Value::to_string).collect();
+    Ok(Value::String(parts.join(&separator).into()))
+}
@@ -1,61 +1,339 @@
 use std::fmt::Display;
+use std::rc::Rc;
+use std::time::Duration;
 use std::{
-    fs::File,
-    io::{Read, Write},
-    process,
+    fs,
+    io::{self, Write},
 };
 
-use crate::interpreter::interpret;
+use crate::ast::Expr;
+use crate::capabilities::{Capabilities, Capability};
+use crate::class::ClassBuilder;
+use crate::config::{Backend, Config};
+use crate::depth;
+use crate::error::Error;
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::optimizer::optimize;
 use crate::parser::parse;
 use crate::scanner::scan;
+use crate::stdlib::{Context, BUILTINS};
+use crate::symbol::Symbol;
+use crate::value::{NativeFunction, Value};
+use crate::vm::compiler::compile;
+use crate::vm::debug::disassemble_chunk;
+use crate::vm::Vm;
 
 pub struct Lox {
-    had_error: bool,
+    config: Config,
+    interpreter: Interpreter,
+    vm: Vm,
+    context: Context,
+    stdout: Box<dyn Write>,
+    stderr: Box<dyn Write>,
 }
 
-impl Lox {
-    pub fn new() -> Lox {
-        Lox { had_error: false }
+pub struct LoxBuilder {
+    config: Config,
+    stdout: Box<dyn Write>,
+    stderr: Box<dyn Write>,
+    // Natives need the context of the interpreter being built, so globals are
+    // defined in order once it exists.
+    globals: Vec<Definition>,
+}
+
+type Definition = Box<dyn FnOnce(&mut Lox)>;
+
+impl LoxBuilder {
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
     }
 
-    pub fn run_file(&mut self, path: &str) {
-        // Open the file
-        let mut file = match File::open(path) {
-            Ok(file) => file,
-            Err(error) => {
-                println!("Could not open file: {}", error);
-                process::exit(65);
-            }
+    pub fn optimize(mut self, optimize: bool) -> Self {
+        self.config.optimize = optimize;
+        self
+    }
+
+    pub fn dump_ast(mut self, dump_ast: bool) -> Self {
+        self.config.dump_ast = dump_ast;
+        self
+    }
+
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.config.trace = trace;
+        self
+    }
+
+    // The garbage collector options only affect the VM, since the tree-walker
+    // leaves memory to Rust.
+    pub fn gc_stress(mut self, gc_stress: bool) -> Self {
+        self.config.gc_stress = gc_stress;
+        self
+    }
+
+    pub fn gc_stats(mut self, gc_stats: bool) -> Self {
+        self.config.gc_stats = gc_stats;
+        self
+    }
+
+    pub fn max_heap(mut self, max_heap: usize) -> Self {
+        self.config.max_heap = Some(max_heap);
+        self
+    }
+
+    pub fn max_parse_depth(mut self, max_parse_depth: usize) -> Self {
+        self.config.max_parse_depth = max_parse_depth;
+        self
+    }
+
+    // Limits how deeply evaluation may nest, counting the calls that builtins
+    // like `map` make back into values.
+    pub fn max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.config.max_call_depth = max_call_depth;
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: u64) -> Self {
+        self.config.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.config.capabilities = capabilities;
+        self
+    }
+
+    pub fn prelude(mut self, prelude: bool) -> Self {
+        self.config.prelude = prelude;
+        self
+    }
+
+    pub fn stdout(mut self, stdout: impl Write + 'static) -> Self {
+        self.stdout = Box::new(stdout);
+        self
+    }
+
+    pub fn stderr(mut self, stderr: impl Write + 'static) -> Self {
+        self.stderr = Box::new(stderr);
+        self
+    }
+
+    pub fn global(mut self, name: &str, value: Value) -> Self {
+        let name = name.to_string();
+        self.globals
+            .push(Box::new(move |lox| lox.set_global(&name, value)));
+        self
+    }
+
+    pub fn native(
+        mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&Context, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    ) -> Self {
+        let name = name.to_string();
+        self.globals.push(Box::new(move |lox| {
+            lox.define_native(&name, arity, function)
+        }));
+        self
+    }
+
+    pub fn class<T: 'static>(mut self, class: ClassBuilder<T>) -> Self {
+        self.globals
+            .push(Box::new(move |lox| lox.define_class(class)));
+        self
+    }
+
+    pub fn build(self) -> Lox {
+        let vm = Vm::new(&self.config);
+        let context = Context {
+            capabilities: self.config.capabilities,
+            gc_stats: vm.heap().stats_handle(),
+        };
+        let mut lox = Lox {
+            interpreter: Interpreter::new(&self.config),
+            vm,
+            config: self.config,
+            context,
+            stdout: self.stdout,
+            stderr: self.stderr,
         };
 
-        // Load the file into a string
-        let contents: &mut String = &mut String::new();
-        match file.read_to_string(contents) {
-            Ok(_) => (),
-            Err(error) => {
-                println!("Could not read file: {}", error);
-                process::exit(66);
+        if lox.config.prelude {
+            for builtin in &BUILTINS {
+                let native = builtin.native(&lox.context);
+                lox.define_global(Symbol::intern(builtin.name()), Value::Native(native));
             }
+        }
+
+        for define in self.globals {
+            define(&mut lox);
+        }
+
+        lox
+    }
+}
+
+impl Lox {
+    pub fn builder() -> LoxBuilder {
+        LoxBuilder {
+            config: Config::default(),
+            stdout: Box::new(io::stdout()),
+            stderr: Box::new(io::stderr()),
+            globals: Vec::new(),
+        }
+    }
+
+    pub fn new() -> Lox {
+        Lox::builder().build()
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.define_global(Symbol::intern(name), value);
+    }
+
+    // Makes a host function callable from scripts as a global, in both backends.
+    // It gets the same context as the builtins, so it can check capabilities
+    // before touching a host resource.
+    pub fn define_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        function: impl Fn(&Context, &[Value]) -> Result<Value, RuntimeError> + 'static,
+    ) {
+        let context = self.context.clone();
+        let native = move |arguments: &[Value]| function(&context, arguments);
+        let native = NativeFunction::new(name, arity, Box::new(native));
+        self.set_global(name, Value::Native(Rc::new(native)));
+    }
+
+    // Exposes a host type to scripts as a class whose instances wrap a `T`.
+    pub fn define_class<T: 'static>(&mut self, class: ClassBuilder<T>) {
+        let class = class.build();
+        self.define_global(class.name(), Value::Class(Rc::new(class)));
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let name = Symbol::lookup(name)?;
+        match self.config.backend {
+            Backend::TreeWalker => self.interpreter.global(name),
+            Backend::Vm => self.vm.global(name),
+        }
+    }
+
+    // Calls a global function from the host, e.g. a callback the embedder
+    // looks up by name.
+    pub fn call(&mut self, name: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        match self.get_global(name) {
+            Some(callee) => self
+                .call_value(&callee, arguments)
+                .map_err(|error| error.in_call(callee.callee_name(), None)),
+            None => Err(RuntimeError::UndefinedGlobal(name.to_string())),
+        }
+    }
+
+    // Calls a method on a value from the host, e.g. a handler on an instance
+    // or `push` on a list.
+    pub fn call_method(
+        &mut self,
+        receiver: &Value,
+        name: &str,
+        arguments: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        let method = match receiver {
+            // Every method of a host class was interned when the class was
+            // built, so a name that isn't interned can't be one of them.
+            Value::Instance(instance) => Symbol::lookup(name)
+                .and_then(|name| instance.get(name))
+                .transpose()?,
+            _ => receiver.builtin_method(name),
+        };
+        let Some(method) = method else {
+            return Err(RuntimeError::UndefinedMethod(
+                receiver.type_name(),
+                name.to_string(),
+            ));
         };
+        self.call_value(&method, arguments)
+            .map_err(|error| error.in_call(method.callee_name(), None))
+    }
+
+    fn call_value(&self, callee: &Value, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        depth::limit(self.config.max_call_depth, || callee.call(arguments))
+    }
 
-        self.run(contents);
-        if self.had_error {
-            process::exit(65);
+    // Only the backend in use holds globals, so the other one never keeps host
+    // values alive.
+    fn define_global(&mut self, name: Symbol, value: Value) {
+        match self.config.backend {
+            Backend::TreeWalker => self.interpreter.define_global(name, value),
+            Backend::Vm => self.vm.define_global(name, value),
         }
     }
 
-    pub fn run_prompt(&mut self) {
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let tokens = scan(source).map_err(Error::Scan)?;
+        let expr = parse(tokens, self.config.max_parse_depth)?;
+        let expr = self.prepare(expr)?;
+
+        match self.config.backend {
+            Backend::TreeWalker => Ok(self.interpreter.evaluate(&expr)?),
+            Backend::Vm => {
+                if self.config.trace {
+                    self.check_stdout()?;
+                }
+                let chunk = compile(&expr, self.vm.heap_mut())?;
+                Ok(self.vm.execute(&chunk, &mut self.stdout)?)
+            }
+        }
+    }
+
+    // Errors are returned rather than reported, so that the caller can decide
+    // how to exit.
+    pub fn run_file(&mut self, path: &str) -> Result<(), Error> {
+        let contents = read_file(path)?;
+
+        let result = self.eval(&contents).and_then(|value| self.print(&value));
+        let stats = self.report_gc_stats();
+        result.and(stats)
+    }
+
+    pub fn disassemble_file(&mut self, path: &str) -> Result<(), Error> {
+        let contents = read_file(path)?;
+
+        let tokens = scan(&contents).map_err(Error::Scan)?;
+        let expr = parse(tokens, self.config.max_parse_depth)?;
+        let expr = self.prepare(expr)?;
+        let chunk = compile(&expr, self.vm.heap_mut())?;
+        self.check_stdout()?;
+        disassemble_chunk(&mut self.stdout, &chunk, self.vm.heap(), "<script>")
+            .map_err(Error::Write)
+    }
+
+    // Errors in the lines read are reported, but failing to write the prompt
+    // ends the session.
+    pub fn run_prompt(&mut self) -> Result<(), Error> {
         loop {
-            print!("> ");
-            match std::io::stdout().flush() {
-                Ok(_) => (),
-                Err(_) => panic!("Error writing to stdout"),
+            // Without stdout the prompt still reads lines, it just can't show
+            // the marker or the results.
+            if self.check_stdout().is_ok() {
+                write!(self.stdout, "> ").map_err(Error::Write)?;
+                self.stdout.flush().map_err(Error::Write)?;
             }
             let mut line = String::new();
 
-            match std::io::stdin().read_line(&mut line) {
+            match io::stdin().read_line(&mut line) {
+                Ok(0) => break,
                 Ok(_) => (),
-                Err(_) => panic!("Error reading user input"),
+                Err(error) => {
+                    self.error(&error);
+                    break;
+                }
             };
 
             let line = line.trim();
@@ -63,32 +341,63 @@ impl Lox {
                 continue;
             }
             self.run(line);
-            self.had_error = false;
         }
+
+        self.report_gc_stats()
     }
 
     pub fn run(&mut self, source: &str) {
-        let tokens = scan(source);
-        match tokens {
-            Ok(tokens) => {
-                let expr = parse(tokens);
-                match expr {
-                    Ok(expr) => {
-                        interpret(&expr).unwrap_or_else(|e| self.error(&e));
-                    }
-                    Err(error) => {
-                        self.error(&error);
-                    }
-                }
-            }
-            Err(error) => {
-                self.error(&error);
-            }
+        if let Err(error) = self.eval(source).and_then(|value| self.print(&value)) {
+            self.error(&error);
+        }
+    }
+
+    fn print(&mut self, value: &Value) -> Result<(), Error> {
+        self.check_stdout()?;
+        writeln!(self.stdout, "{}", value).map_err(Error::Write)
+    }
+
+    // Every write to stdout needs the capability, including the debugging
+    // output of `--trace`, `--dump-optimized-ast` and `disasm`.
+    fn check_stdout(&self) -> Result<(), Error> {
+        Ok(self.config.capabilities.check(Capability::Stdout)?)
+    }
+
+    fn prepare(&mut self, expr: Expr) -> Result<Expr, Error> {
+        let expr = if self.config.optimize {
+            optimize(&expr)
+        } else {
+            expr
+        };
+
+        if self.config.dump_ast {
+            self.check_stdout()?;
+            writeln!(self.stdout, "{}", expr).map_err(Error::Write)?;
         }
+
+        Ok(expr)
     }
 
+    fn report_gc_stats(&mut self) -> Result<(), Error> {
+        if self.config.gc_stats {
+            writeln!(self.stderr, "{}", self.vm.heap().stats()).map_err(Error::Write)?;
+        }
+        Ok(())
+    }
+
+    // There is nowhere left to report a failure to write an error, so it is
+    // dropped.
     pub fn error(&mut self, error: &impl Display) {
-        println!("{}", error);
-        self.had_error = true;
+        let _ = writeln!(self.stderr, "{}", error);
+    }
+}
+
+fn read_file(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|error| Error::Read(path.to_string(), error))
+}
+
+impl Default for Lox {
+    fn default() -> Lox {
+        Lox::new()
     }
 }
@@ -1,24 +1,167 @@
+use std::time::Duration;
 use std::{env, process};
 
-use lox::Lox;
-
-mod ast;
-mod interpreter;
-mod lox;
-pub mod parser;
-mod scanner;
-mod token;
+use rust_lox::{Backend, Capabilities, Capability, Error, Lox, LoxBuilder, BUILTINS};
 
 fn main() {
-    let mut lox: Lox = Lox::new();
+    let mut builder: LoxBuilder = Lox::builder();
+    let mut capabilities = Capabilities::all();
+    let mut backend = Backend::TreeWalker;
+    // Only the VM has a heap, so the garbage collector options need it.
+    let mut gc_options = false;
+    let mut script: Option<String> = None;
+    let mut args = env::args().skip(1).peekable();
+
+    let disassemble = args.peek().map(String::as_str) == Some("disasm");
+    if disassemble {
+        args.next();
+    }
+
+    for arg in args {
+        if arg == "--help" {
+            help();
+        } else if arg == "--help-builtins" {
+            help_builtins();
+        } else if arg == "--trace" {
+            builder = builder.trace(true);
+        } else if arg == "-O" {
+            builder = builder.optimize(true);
+        } else if arg == "--dump-optimized-ast" {
+            builder = builder.dump_ast(true);
+        } else if let Some(depth) = arg.strip_prefix("--max-parse-depth=") {
+            builder = builder.max_parse_depth(depth.parse().unwrap_or_else(|_| usage()));
+        } else if let Some(depth) = arg.strip_prefix("--max-call-depth=") {
+            builder = builder.max_call_depth(depth.parse().unwrap_or_else(|_| usage()));
+        } else if let Some(steps) = arg.strip_prefix("--max-steps=") {
+            builder = builder.max_steps(steps.parse().unwrap_or_else(|_| usage()));
+        } else if let Some(timeout) = arg.strip_prefix("--timeout=") {
+            builder = builder.timeout(parse_duration(timeout).unwrap_or_else(|| usage()));
+        } else if let Some(names) = arg.strip_prefix("--allow=") {
+            capabilities = Capabilities::none();
+            for capability in parse_capabilities(names) {
+                capabilities.grant(capability);
+            }
+        } else if let Some(names) = arg.strip_prefix("--deny=") {
+            for capability in parse_capabilities(names) {
+                capabilities.revoke(capability);
+            }
+        } else if arg == "--gc-stress" {
+            builder = builder.gc_stress(true);
+            gc_options = true;
+        } else if arg == "--gc-stats" {
+            builder = builder.gc_stats(true);
+            gc_options = true;
+        } else if let Some(size) = arg.strip_prefix("--max-heap=") {
+            builder = builder.max_heap(parse_size(size).unwrap_or_else(|| usage()));
+            gc_options = true;
+        } else if let Some(name) = arg.strip_prefix("--backend=") {
+            backend = match name {
+                "tree" => Backend::TreeWalker,
+                "vm" => Backend::Vm,
+                _ => usage(),
+            };
+        } else if arg.starts_with('-') || script.is_some() {
+            usage();
+        } else {
+            script = Some(arg);
+        }
+    }
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        println!("Usage: rust-lox [script]");
+    if gc_options && backend != Backend::Vm {
+        eprintln!("--gc-stress, --gc-stats and --max-heap need --backend=vm");
         process::exit(64);
-    } else if args.len() == 2 {
-        lox.run_file(&args[1]);
+    }
+
+    let mut lox: Lox = builder.backend(backend).capabilities(capabilities).build();
+
+    let result = match script {
+        Some(path) if disassemble => lox.disassemble_file(&path),
+        Some(path) => lox.run_file(&path),
+        None if disassemble => usage(),
+        None => lox.run_prompt(),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(exit_code(&error));
+    }
+}
+
+// Exit codes follow sysexits.h.
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Read(..) => 66,
+        Error::Write(_) => 74,
+        _ => 65,
+    }
+}
+
+// Parses a byte count with an optional K, M or G suffix, e.g. "64M".
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, multiplier) = match size.chars().last()?.to_ascii_uppercase() {
+        'K' => (&size[..size.len() - 1], 1024),
+        'M' => (&size[..size.len() - 1], 1024 * 1024),
+        'G' => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+
+    digits.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+fn parse_capabilities(names: &str) -> Vec<Capability> {
+    names
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| Capability::from_name(name).unwrap_or_else(|| usage()))
+        .collect()
+}
+
+// Parses a duration in milliseconds, or in seconds with an "s" suffix.
+fn parse_duration(duration: &str) -> Option<Duration> {
+    if let Some(millis) = duration.strip_suffix("ms") {
+        millis.parse().ok().map(Duration::from_millis)
+    } else if let Some(secs) = duration.strip_suffix('s') {
+        secs.parse().ok().map(Duration::from_secs_f64)
     } else {
-        lox.run_prompt();
+        duration.parse().ok().map(Duration::from_millis)
+    }
+}
+
+fn help_builtins() -> ! {
+    println!("Built-in functions:");
+    for builtin in &BUILTINS {
+        println!("  {:<18} {}", builtin.signature(), builtin.description());
     }
+    process::exit(0);
+}
+
+const USAGE: &str = "\
+Usage: rust-lox [--backend=tree|vm] [-O] [--dump-optimized-ast] [--trace] [script]
+                [--max-parse-depth=N] [--max-call-depth=N]
+                [--max-steps=N] [--timeout=DURATION]
+                [--allow=CAPABILITIES] [--deny=CAPABILITIES]
+                [--gc-stress] [--gc-stats] [--max-heap=SIZE]
+       rust-lox disasm FILE
+       rust-lox --help
+       rust-lox --help-builtins
+
+Capabilities: fs-read, fs-write, env, clock, exit, stdin, stdout
+  All are granted unless narrowed with --allow or --deny.
+  clock and stdin gate the clock() and input() builtins, and stdout gates all
+  output, including --trace, --dump-optimized-ast and disasm. fs-read, fs-write,
+  env and exit are accepted but inert: no builtin uses those resources yet.
+
+GC options: --gc-stress, --gc-stats and --max-heap only apply to the VM, so they
+  need --backend=vm.
+";
+
+fn help() -> ! {
+    print!("{}", USAGE);
+    process::exit(0);
+}
+
+// Invalid arguments are an error, so the usage goes to stderr.
+fn usage() -> ! {
+    eprint!("{}", USAGE);
+    process::exit(64);
 }
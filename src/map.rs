@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::UnhashableKey;
+use crate::list;
+use crate::value::{NativeFunction, Value};
+
+pub type Map = Rc<RefCell<Table<Key, Value>>>;
+
+type MapMethod = fn(&Map, &[Value]) -> Result<Value, RuntimeError>;
+
+// Name, arity and implementation.
+const METHODS: [(&str, usize, MapMethod); 6] = [
+    ("keys", 0, keys),
+    ("values", 0, values),
+    ("entries", 0, entries),
+    ("has", 1, has),
+    ("remove", 1, remove),
+    ("len", 0, len),
+];
+
+// Only values compared by content can be keys. Numbers are keyed by their bits,
+// with zero normalized so that `-0` and `0` find the same entry.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Nil,
+    Boolean(bool),
+    Number(u64),
+    String(Rc<str>),
+}
+
+impl Key {
+    pub fn new(value: &Value) -> Result<Key, RuntimeError> {
+        match value {
+            Value::Nil => Ok(Key::Nil),
+            Value::Boolean(b) => Ok(Key::Boolean(*b)),
+            Value::Number(n) => Ok(Key::Number(number_bits(*n)?)),
+            Value::String(s) => Ok(Key::String(Rc::clone(s))),
+            other => Err(UnhashableKey(other.type_name())),
+        }
+    }
+
+    pub fn value(&self) -> Value {
+        match self {
+            Key::Nil => Value::Nil,
+            Key::Boolean(b) => Value::Boolean(*b),
+            Key::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            Key::String(s) => Value::String(Rc::clone(s)),
+        }
+    }
+}
+
+pub fn number_bits(n: f64) -> Result<u64, RuntimeError> {
+    if n.is_nan() {
+        Err(UnhashableKey("NaN"))
+    } else {
+        Ok((n + 0.0).to_bits())
+    }
+}
+
+// Entries are kept in insertion order so that printing and iterating a map
+// gives the same result on every run.
+pub struct Table<K, V> {
+    entries: Vec<(K, V)>,
+    positions: HashMap<K, usize>,
+}
+
+impl<K, V> Default for Table<K, V> {
+    fn default() -> Self {
+        Table {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Table<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.positions
+            .get(key)
+            .map(|&position| &self.entries[position].1)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        match self.positions.get(&key) {
+            Some(&position) => self.entries[position].1 = value,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for (key, _) in &self.entries[position..] {
+            if let Some(later) = self.positions.get_mut(key) {
+                *later -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+pub fn new(entries: Vec<(Value, Value)>) -> Result<Value, RuntimeError> {
+    let mut table = Table::default();
+    for (key, value) in entries {
+        table.insert(Key::new(&key)?, value);
+    }
+    Ok(Value::Map(Rc::new(RefCell::new(table))))
+}
+
+// Looks up a method on a map, returning it bound to the map.
+pub fn method(receiver: &Map, name: &str) -> Option<Value> {
+    let (name, arity, function) = METHODS.into_iter().find(|(method, _, _)| *method == name)?;
+    let receiver = Rc::clone(receiver);
+    let bound = move |arguments: &[Value]| function(&receiver, arguments);
+    let native = NativeFunction::new(name, arity, Box::new(bound));
+    Some(Value::Native(Rc::new(native)))
+}
+
+fn keys(map: &Map, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(list::new(
+        map.borrow().iter().map(|(key, _)| key.value()).collect(),
+    ))
+}
+
+fn values(map: &Map, _: &[Value]) -> Result<Value, RuntimeError> {
+    let values = map
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+    Ok(list::new(values))
+}
+
+// Each entry becomes a `[key, value]` list, so maps can be walked with the list
+// methods.
+fn entries(map: &Map, _: &[Value]) -> Result<Value, RuntimeError> {
+    let entries = map
+        .borrow()
+        .iter()
+        .map(|(key, value)| list::new(vec![key.value(), value.clone()]))
+        .collect();
+    Ok(list::new(entries))
+}
+
+fn has(map: &Map, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let key = Key::new(&arguments[0])?;
+    Ok(Value::Boolean(map.borrow().get(&key).is_some()))
+}
+
+fn remove(map: &Map, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let key = Key::new(&arguments[0])?;
+    let removed = map.borrow_mut().remove(&key);
+    Ok(removed.unwrap_or(Value::Nil))
+}
+
+fn len(map: &Map, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(map.borrow().len() as f64))
+}
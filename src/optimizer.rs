@@ -0,0 +1,139 @@
+use crate::ast::Expr::{
+    Binary, Call, Get, Grouping, Index, Interpolation, List, Literal, Map, Set, SetIndex, Unary,
+    Variable,
+};
+use crate::ast::{
+    BinaryExpr, CallExpr, Expr, GetExpr, IndexExpr, InterpolationExpr, ListExpr, MapExpr, SetExpr,
+    SetIndexExpr, UnaryExpr, VariableExpr,
+};
+use crate::config::Config;
+use crate::interpreter::Interpreter;
+use crate::token::TokenType;
+
+pub fn optimize(expr: &Expr) -> Expr {
+    match expr {
+        Binary(b) => {
+            let folded = Binary(BinaryExpr::new(
+                optimize(b.left()),
+                b.op().clone(),
+                optimize(b.right()),
+            ));
+
+            match &folded {
+                Binary(b) if is_literal(b.left()) && is_literal(b.right()) => fold(folded),
+                _ => folded,
+            }
+        }
+        Call(c) => Call(CallExpr::new(
+            optimize(c.callee()),
+            c.paren().clone(),
+            c.arguments().iter().map(optimize).collect(),
+        )),
+        Get(g) => Get(GetExpr::new(optimize(g.object()), g.name().clone())),
+        // Groupings only matter to the parser; the tree already encodes precedence.
+        Grouping(g) => optimize(g.expression()),
+        Index(i) => Index(IndexExpr::new(
+            optimize(i.object()),
+            i.bracket().clone(),
+            optimize(i.index()),
+        )),
+        Interpolation(i) => {
+            let parts: Vec<Expr> = i.parts().iter().map(optimize).collect();
+            let all_literal = parts.iter().all(is_literal);
+            let folded = Interpolation(InterpolationExpr::new(i.token().clone(), parts));
+
+            if all_literal {
+                fold(folded)
+            } else {
+                folded
+            }
+        }
+        List(l) => List(ListExpr::new(
+            l.bracket().clone(),
+            l.elements().iter().map(optimize).collect(),
+        )),
+        Literal(l) => Literal(l.clone()),
+        Map(m) => Map(MapExpr::new(
+            m.brace().clone(),
+            m.entries()
+                .iter()
+                .map(|(key, value)| (optimize(key), optimize(value)))
+                .collect(),
+        )),
+        Set(s) => Set(SetExpr::new(
+            optimize(s.object()),
+            s.name().clone(),
+            optimize(s.value()),
+        )),
+        SetIndex(s) => SetIndex(SetIndexExpr::new(
+            optimize(s.object()),
+            s.bracket().clone(),
+            optimize(s.index()),
+            optimize(s.value()),
+        )),
+        Unary(u) => {
+            let operand = optimize(u.expr());
+
+            match (u.op().token_type(), &operand) {
+                (_, Literal(_)) => fold(Unary(UnaryExpr::new(u.op().clone(), operand))),
+                // `!!x` is `x` when `x` already evaluates to a boolean.
+                (TokenType::Bang, Unary(inner))
+                    if inner.op() == TokenType::Bang && is_boolean(inner.expr()) =>
+                {
+                    optimize(inner.expr())
+                }
+                // `-(-x)` is `x` when `x` already evaluates to a number.
+                (TokenType::Minus, Unary(inner))
+                    if inner.op() == TokenType::Minus && is_number(inner.expr()) =>
+                {
+                    optimize(inner.expr())
+                }
+                _ => Unary(UnaryExpr::new(u.op().clone(), operand)),
+            }
+        }
+        Variable(v) => Variable(VariableExpr::new(v.name().clone())),
+    }
+}
+
+// Evaluates a constant expression, leaving it in place if it fails so that the
+// error is still reported at runtime.
+fn fold(expr: Expr) -> Expr {
+    match Interpreter::new(&Config::default()).evaluate(&expr) {
+        Ok(value) => match value.to_literal() {
+            Some(literal) => Literal(literal),
+            None => expr,
+        },
+        Err(_) => expr,
+    }
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Literal(_))
+}
+
+fn is_boolean(expr: &Expr) -> bool {
+    match expr {
+        Binary(b) => matches!(
+            b.op().token_type(),
+            TokenType::EqualEqual
+                | TokenType::BangEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+        ),
+        Unary(u) => u.op() == TokenType::Bang,
+        _ => false,
+    }
+}
+
+fn is_number(expr: &Expr) -> bool {
+    match expr {
+        Binary(b) => matches!(
+            b.op().token_type(),
+            TokenType::Minus | TokenType::Star | TokenType::Slash
+        ),
+        Unary(u) => u.op() == TokenType::Minus,
+        _ => false,
+    }
+}
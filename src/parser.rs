@@ -1,17 +1,27 @@
-use crate::ast::Expr::{Grouping, Literal};
-use crate::ast::{BinaryExpr, GroupingExpr, LiteralExpr, UnaryExpr};
-use crate::parser::ParseError::{ExpectedExpression, ExpectedToken};
+use crate::ast::Expr::{Grouping, Literal, Variable};
+use crate::ast::{
+    BinaryExpr, CallExpr, GetExpr, GroupingExpr, IndexExpr, InterpolationExpr, ListExpr,
+    LiteralExpr, MapExpr, SetExpr, SetIndexExpr, UnaryExpr, VariableExpr,
+};
+use crate::parser::ParseError::{
+    ExpectedExpression, ExpectedPropertyName, ExpectedToken, InvalidAssignmentTarget, StackOverflow,
+};
 use crate::{
     ast::Expr,
     token::{Token, TokenType},
 };
 use std::fmt::{Display, Formatter};
 use std::iter::Peekable;
+use std::rc::Rc;
 use std::slice::Iter;
 
+#[derive(Debug)]
 pub enum ParseError {
     ExpectedToken(TokenType),
     ExpectedExpression(),
+    StackOverflow(),
+    ExpectedPropertyName(),
+    InvalidAssignmentTarget(Token),
 }
 
 impl Display for ParseError {
@@ -19,33 +29,61 @@ impl Display for ParseError {
         match self {
             ExpectedToken(token_type) => write!(f, "Expected token of type {:?}", token_type),
             ExpectedExpression() => write!(f, "Expected expression"),
+            StackOverflow() => write!(f, "Stack overflow"),
+            ExpectedPropertyName() => write!(f, "Expected property name after '.'"),
+            InvalidAssignmentTarget(token) => write!(f, "Invalid assignment target: {}", token),
         }
     }
 }
 
-pub fn parse(tokens: Vec<Token>) -> Result<Expr, ParseError> {
-    let mut ctx = ParseCtx::new(&tokens);
+pub fn parse(tokens: Vec<Token>, max_depth: usize) -> Result<Expr, ParseError> {
+    let mut ctx = ParseCtx::new(&tokens, max_depth);
     expression(&mut ctx)
 }
 
 struct ParseCtx<'a> {
     tokens: Peekable<Iter<'a, Token>>,
+    depth: usize,
+    max_depth: usize,
 }
 
 impl<'a> ParseCtx<'a> {
-    pub fn new(tokens: &'a Vec<Token>) -> ParseCtx<'a> {
+    pub fn new(tokens: &'a [Token], max_depth: usize) -> ParseCtx<'a> {
         ParseCtx {
             tokens: tokens.iter().peekable(),
+            depth: 0,
+            max_depth,
         }
     }
 
+    // Tracks how deeply the expression tree nests so that both the parser and
+    // later recursive passes fail cleanly instead of overflowing the native stack.
+    fn descend(&mut self) -> Result<(), ParseError> {
+        if self.depth >= self.max_depth {
+            return Err(StackOverflow());
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn nested<T>(
+        &mut self,
+        rule: fn(&mut ParseCtx<'a>) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        self.descend()?;
+        let result = rule(self);
+        self.depth -= 1;
+        result
+    }
+
     fn next(&mut self) -> Option<Token> {
-        self.tokens.next().map(|t| t.clone())
+        self.tokens.next().cloned()
     }
 
     fn peek(&mut self) -> Option<Token> {
         if let Some(token) = self.tokens.peek() {
-            let x1: &Token = *token;
+            let x1: &Token = token;
             let x2: Token = x1.clone();
             Some(x2)
         } else {
@@ -77,22 +115,50 @@ impl<'a> ParseCtx<'a> {
 }
 
 fn expression(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
-    equality(ctx)
+    assignment(ctx)
+}
+
+fn assignment(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
+    let expr = equality(ctx)?;
+
+    if let Some(equals) = ctx.read_token_if(&TokenType::Equal) {
+        let value = ctx.nested(assignment)?;
+        return match expr {
+            Expr::Get(get) => {
+                let name = get.name().clone();
+                Ok(Expr::Set(SetExpr::new(get.into_object(), name, value)))
+            }
+            Expr::Index(index) => {
+                let bracket = index.bracket().clone();
+                let (object, index) = index.into_operands();
+                Ok(Expr::SetIndex(SetIndexExpr::new(
+                    object, bracket, index, value,
+                )))
+            }
+            _ => Err(InvalidAssignmentTarget(equals)),
+        };
+    }
+
+    Ok(expr)
 }
 
 fn equality(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
     let mut expr = comparison(ctx)?;
+    let depth = ctx.depth;
 
-    while let Some(op) = ctx.read_token_if_any(&[TokenType::Equal, TokenType::EqualEqual]) {
+    while let Some(op) = ctx.read_token_if_any(&[TokenType::BangEqual, TokenType::EqualEqual]) {
+        ctx.descend()?;
         let right = comparison(ctx)?;
         expr = Expr::Binary(BinaryExpr::new(expr, op, right));
     }
 
+    ctx.depth = depth;
     Ok(expr)
 }
 
 fn comparison(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
     let mut expr = term(ctx)?;
+    let depth = ctx.depth;
 
     while let Some(op) = ctx.read_token_if_any(&[
         TokenType::Greater,
@@ -100,21 +166,26 @@ fn comparison(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
         TokenType::Less,
         TokenType::LessEqual,
     ]) {
+        ctx.descend()?;
         let right = term(ctx)?;
         expr = Expr::Binary(BinaryExpr::new(expr, op.clone(), right));
     }
 
+    ctx.depth = depth;
     Ok(expr)
 }
 
 fn term(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
     let mut expr = factor(ctx)?;
+    let depth = ctx.depth;
 
     while let Some(op) = ctx.read_token_if_any(&[TokenType::Minus, TokenType::Plus]) {
+        ctx.descend()?;
         let right = factor(ctx)?;
         expr = Expr::Binary(BinaryExpr::new(expr, op.clone(), right));
     }
 
+    ctx.depth = depth;
     Ok(expr)
 }
 
@@ -122,7 +193,7 @@ fn factor(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
     let mut expr = unary(ctx)?;
 
     if let Some(op) = ctx.read_token_if_any(&[TokenType::Star, TokenType::Slash]) {
-        let right = factor(ctx)?;
+        let right = ctx.nested(factor)?;
         expr = Expr::Binary(BinaryExpr::new(expr, op.clone(), right));
     }
 
@@ -131,10 +202,91 @@ fn factor(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
 
 fn unary(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
     if let Some(op) = ctx.read_token_if_any(&[TokenType::Bang, TokenType::Minus]) {
-        let right = unary(ctx)?;
+        let right = ctx.nested(unary)?;
         Ok(Expr::Unary(UnaryExpr::new(op.clone(), right)))
     } else {
-        primary(ctx)
+        call(ctx)
+    }
+}
+
+fn call(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
+    let mut expr = primary(ctx)?;
+    let depth = ctx.depth;
+
+    while let Some(token) =
+        ctx.read_token_if_any(&[TokenType::LeftParen, TokenType::LeftBracket, TokenType::Dot])
+    {
+        ctx.descend()?;
+        if token == TokenType::LeftParen {
+            let (arguments, paren) = expression_list(ctx, TokenType::RightParen)?;
+            expr = Expr::Call(CallExpr::new(expr, paren, arguments));
+        } else if token == TokenType::LeftBracket {
+            let index = ctx.nested(expression)?;
+            match ctx.read_token_if(&TokenType::RightBracket) {
+                Some(bracket) => expr = Expr::Index(IndexExpr::new(expr, bracket, index)),
+                None => return Err(ExpectedToken(TokenType::RightBracket)),
+            }
+        } else {
+            match ctx.next() {
+                Some(name) if matches!(name.token_type(), TokenType::Identifier(_)) => {
+                    expr = Expr::Get(GetExpr::new(expr, name));
+                }
+                _ => return Err(ExpectedPropertyName()),
+            }
+        }
+    }
+
+    ctx.depth = depth;
+    Ok(expr)
+}
+
+fn map(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
+    let mut entries = Vec::new();
+
+    if let Some(brace) = ctx.read_token_if(&TokenType::RightBrace) {
+        return Ok(Expr::Map(MapExpr::new(brace, entries)));
+    }
+
+    loop {
+        let key = ctx.nested(expression)?;
+        if ctx.read_token_if(&TokenType::Colon).is_none() {
+            return Err(ExpectedToken(TokenType::Colon));
+        }
+        let value = ctx.nested(expression)?;
+        entries.push((key, value));
+        if ctx.read_token_if(&TokenType::Comma).is_none() {
+            break;
+        }
+    }
+
+    match ctx.read_token_if(&TokenType::RightBrace) {
+        Some(brace) => Ok(Expr::Map(MapExpr::new(brace, entries))),
+        None => Err(ExpectedToken(TokenType::RightBrace)),
+    }
+}
+
+// Parses comma-separated expressions up to `closing`, for call arguments and
+// list literals.
+fn expression_list(
+    ctx: &mut ParseCtx,
+    closing: TokenType,
+) -> Result<(Vec<Expr>, Token), ParseError> {
+    let mut expressions = Vec::new();
+
+    if let Some(token) = ctx.read_token_if(&closing) {
+        return Ok((expressions, token));
+    }
+
+    loop {
+        expressions.push(ctx.nested(expression)?);
+        if ctx.read_token_if(&TokenType::Comma).is_none() {
+            break;
+        }
+    }
+
+    match ctx.read_token_if(&closing) {
+        Some(token) => Ok((expressions, token)),
+        None => Err(ExpectedToken(closing)),
     }
 }
 
@@ -146,10 +298,18 @@ fn primary(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
         TokenType::False => Ok(Literal(LiteralExpr::Boolean(false))),
         TokenType::Nil => Ok(Literal(LiteralExpr::Nil())),
         TokenType::Number(n) => Ok(Literal(LiteralExpr::Number(*n))),
-        TokenType::String(s) => Ok(Literal(LiteralExpr::String(s.to_string()))),
+        TokenType::String(s) => Ok(Literal(LiteralExpr::String(Rc::clone(s)))),
+        TokenType::Identifier(_) => Ok(Variable(VariableExpr::new(token.clone()))),
+        TokenType::Interpolation(_) => interpolation(ctx, token),
+        TokenType::LeftBracket => {
+            let (elements, bracket) = expression_list(ctx, TokenType::RightBracket)?;
+            Ok(Expr::List(ListExpr::new(bracket, elements)))
+        }
+        // Blocks aren't expressions, so a brace here always starts a map.
+        TokenType::LeftBrace => map(ctx),
         TokenType::LeftParen => {
-            let expr = expression(ctx)?;
-            if let Some(_) = ctx.read_token_if(&TokenType::RightParen) {
+            let expr = ctx.nested(expression)?;
+            if ctx.read_token_if(&TokenType::RightParen).is_some() {
                 Ok(Grouping(GroupingExpr::new(expr)))
             } else {
                 Err(ExpectedToken(TokenType::RightParen))
@@ -158,3 +318,31 @@ fn primary(ctx: &mut ParseCtx) -> Result<Expr, ParseError> {
         _ => Err(ExpectedExpression()),
     }
 }
+
+fn interpolation(ctx: &mut ParseCtx, start: Token) -> Result<Expr, ParseError> {
+    let mut parts = Vec::new();
+    let mut token = start.clone();
+
+    loop {
+        match token.token_type() {
+            TokenType::Interpolation(s) => {
+                if !s.is_empty() {
+                    parts.push(Literal(LiteralExpr::String(Rc::clone(s))));
+                }
+                parts.push(ctx.nested(expression)?);
+            }
+            TokenType::String(s) => {
+                if !s.is_empty() {
+                    parts.push(Literal(LiteralExpr::String(Rc::clone(s))));
+                }
+                return Ok(Expr::Interpolation(InterpolationExpr::new(start, parts)));
+            }
+            _ => return Err(ExpectedToken(TokenType::RightBrace)),
+        }
+
+        token = match ctx.next() {
+            Some(token) => token,
+            None => return Err(ExpectedToken(TokenType::RightBrace)),
+        };
+    }
+}
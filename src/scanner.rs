@@ -2,6 +2,7 @@ use crate::token::{Token, TokenType};
 use std::iter::Peekable;
 use std::str::Chars;
 
+use crate::symbol::Symbol;
 use crate::token::Span;
 
 pub fn scan(source: &str) -> Result<Vec<Token>, String> {
@@ -12,8 +13,29 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
         let token = match c {
             '(' => Ok(Some(Token::new(TokenType::LeftParen, span))),
             ')' => Ok(Some(Token::new(TokenType::RightParen, span))),
-            '{' => Ok(Some(Token::new(TokenType::LeftBrace, span))),
-            '}' => Ok(Some(Token::new(TokenType::RightBrace, span))),
+            '{' => {
+                if let Some(depth) = scanner.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                Ok(Some(Token::new(TokenType::LeftBrace, span)))
+            }
+            '}' => match scanner.interpolations.last_mut() {
+                // This brace closes an interpolated expression, so the string resumes.
+                Some(0) => {
+                    scanner.interpolations.pop();
+                    scanner
+                        .string()
+                        .map(|token_type| Some(Token::new(token_type, span)))
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    Ok(Some(Token::new(TokenType::RightBrace, span)))
+                }
+                None => Ok(Some(Token::new(TokenType::RightBrace, span))),
+            },
+            '[' => Ok(Some(Token::new(TokenType::LeftBracket, span))),
+            ']' => Ok(Some(Token::new(TokenType::RightBracket, span))),
+            ':' => Ok(Some(Token::new(TokenType::Colon, span))),
             ',' => Ok(Some(Token::new(TokenType::Comma, span))),
             '.' => Ok(Some(Token::new(TokenType::Dot, span))),
             '-' => Ok(Some(Token::new(TokenType::Minus, span))),
@@ -21,28 +43,28 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
             ';' => Ok(Some(Token::new(TokenType::Semicolon, span))),
             '*' => Ok(Some(Token::new(TokenType::Star, span))),
             '!' => {
-                if scanner.peek_char() == Some('=') {
+                if scanner.read_char_if('=') {
                     Ok(Some(Token::new(TokenType::BangEqual, span)))
                 } else {
                     Ok(Some(Token::new(TokenType::Bang, span)))
                 }
             }
             '=' => {
-                if scanner.peek_char() == Some('=') {
+                if scanner.read_char_if('=') {
                     Ok(Some(Token::new(TokenType::EqualEqual, span)))
                 } else {
                     Ok(Some(Token::new(TokenType::Equal, span)))
                 }
             }
             '<' => {
-                if scanner.peek_char() == Some('=') {
+                if scanner.read_char_if('=') {
                     Ok(Some(Token::new(TokenType::LessEqual, span)))
                 } else {
                     Ok(Some(Token::new(TokenType::Less, span)))
                 }
             }
             '>' => {
-                if scanner.peek_char() == Some('=') {
+                if scanner.read_char_if('=') {
                     Ok(Some(Token::new(TokenType::GreaterEqual, span)))
                 } else {
                     Ok(Some(Token::new(TokenType::Greater, span)))
@@ -59,10 +81,9 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
                 }
             }
             ' ' | '\r' | '\t' | '\n' => Ok(None), // Ignore
-            '"' => match scanner.string() {
-                Ok(string) => Ok(Some(Token::new(TokenType::String(string), span))),
-                Err(error) => Err(error),
-            },
+            '"' => scanner
+                .string()
+                .map(|token_type| Some(Token::new(token_type, span))),
             '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => match scanner.number(c) {
                 Ok(number) => Ok(Some(Token::new(TokenType::Number(number), span))),
                 Err(error) => Err(error),
@@ -73,6 +94,7 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
                 if let Some(token_type) = Scanner::match_keyword(&identifier) {
                     Ok(Some(Token::new(token_type, span)))
                 } else {
+                    let identifier = Symbol::intern(&identifier);
                     Ok(Some(Token::new(TokenType::Identifier(identifier), span)))
                 }
             }
@@ -90,6 +112,10 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
         }
     }
 
+    if !scanner.interpolations.is_empty() {
+        return Err("Unterminated string interpolation".to_owned());
+    }
+
     tokens.push(Token::new(TokenType::Eof, Span::new(scanner.line)));
 
     Ok(tokens)
@@ -98,13 +124,19 @@ pub fn scan(source: &str) -> Result<Vec<Token>, String> {
 struct Scanner<'a> {
     source: Peekable<Chars<'a>>,
     line: usize,
+    // Column of the next character, counted in characters from 1.
+    column: usize,
+    // One entry per open `${`, counting the braces nested inside it.
+    interpolations: Vec<usize>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(source: &'a str) -> Scanner {
+    pub fn new(source: &'a str) -> Scanner<'a> {
         Scanner {
             source: source.chars().peekable(),
             line: 1,
+            column: 1,
+            interpolations: Vec::new(),
         }
     }
 
@@ -113,6 +145,9 @@ impl<'a> Scanner<'a> {
             let span = Span::new(self.line);
             if c == '\n' {
                 self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
             }
             Some((c, span))
         } else {
@@ -124,20 +159,78 @@ impl<'a> Scanner<'a> {
         self.source.peek().copied()
     }
 
-    fn string(&mut self) -> Result<String, String> {
+    pub fn read_char_if(&mut self, expected: char) -> bool {
+        if self.peek_char() == Some(expected) {
+            self.read_char();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Scans up to the end of the string, or up to the next `${`, in which case
+    // the part before it becomes an interpolation token.
+    fn string(&mut self) -> Result<TokenType, String> {
         let mut buffer = String::new();
 
-        for c in self.source.by_ref() {
-            if c == '\n' {
-                self.line += 1;
-            }
-            if c == '"' {
-                return Ok(buffer);
+        loop {
+            let (line, column) = (self.line, self.column);
+            match self.read_char() {
+                Some(('"', _)) => return Ok(TokenType::String(buffer.into())),
+                Some(('$', _)) if self.read_char_if('{') => {
+                    self.interpolations.push(0);
+                    return Ok(TokenType::Interpolation(buffer.into()));
+                }
+                Some(('\\', _)) => buffer.push(self.escape(line, column)?),
+                Some((c, _)) => buffer.push(c),
+                None => return Err("Unterminated string".to_owned()),
             }
-            buffer.push(c);
         }
+    }
+
+    // Reads the rest of an escape sequence whose backslash was at `line` and `column`.
+    fn escape(&mut self, line: usize, column: usize) -> Result<char, String> {
+        let error = |sequence: &str| {
+            format!(
+                "Invalid escape sequence '{}' on line {}, column {}",
+                sequence, line, column
+            )
+        };
 
-        Err("Unterminated string".to_owned())
+        match self.read_char() {
+            Some(('n', _)) => Ok('\n'),
+            Some(('t', _)) => Ok('\t'),
+            Some(('r', _)) => Ok('\r'),
+            Some(('\\', _)) => Ok('\\'),
+            Some(('"', _)) => Ok('"'),
+            Some(('$', _)) => Ok('$'),
+            Some(('0', _)) => Ok('\0'),
+            Some(('u', _)) => {
+                let mut sequence = String::from("\\u");
+                if !self.read_char_if('{') {
+                    return Err(error(&sequence));
+                }
+                sequence.push('{');
+
+                while let Some(c) = self.peek_char().filter(|c| c.is_ascii_hexdigit()) {
+                    sequence.push(c);
+                    self.read_char();
+                }
+                if !self.read_char_if('}') {
+                    return Err(error(&sequence));
+                }
+                sequence.push('}');
+
+                let digits = &sequence[3..sequence.len() - 1];
+                u32::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| error(&sequence))
+            }
+            Some((c, _)) => Err(error(&format!("\\{}", c))),
+            None => Err("Unterminated string".to_owned()),
+        }
     }
 
     fn number(&mut self, first_digit: char) -> Result<f64, String> {
@@ -147,7 +240,7 @@ impl<'a> Scanner<'a> {
         while let Some(digit) = self.source.peek() {
             if digit.is_ascii_digit() {
                 buffer.push(*digit);
-                self.source.next();
+                self.read_char();
             } else {
                 break;
             }
@@ -155,12 +248,12 @@ impl<'a> Scanner<'a> {
 
         if self.source.peek().copied() == Some('.') {
             buffer.push('.');
-            self.source.next();
+            self.read_char();
 
             while let Some(digit) = self.source.peek() {
                 if digit.is_ascii_digit() {
                     buffer.push(*digit);
-                    self.source.next();
+                    self.read_char();
                 } else {
                     break;
                 }
@@ -179,7 +272,7 @@ impl<'a> Scanner<'a> {
         while let Some(c) = self.source.peek().copied() {
             if is_alphanumeric(c) {
                 buffer.push(c);
-                self.source.next();
+                self.read_char();
             } else {
                 break;
             }
@@ -0,0 +1,212 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::capabilities::{Capabilities, Capability};
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::{
+    AssertionFailed, InputError, InvalidNumber, Panic, TypeMismatch,
+};
+use crate::map;
+use crate::value::{NativeFunction, Value};
+use crate::vm::gc::GcStats;
+
+type BuiltinFn = fn(&Context, &[Value]) -> Result<Value, RuntimeError>;
+
+// What builtins and host natives can see of the interpreter they are defined
+// in.
+#[derive(Clone)]
+pub struct Context {
+    pub(crate) capabilities: Capabilities,
+    pub(crate) gc_stats: Rc<RefCell<GcStats>>,
+}
+
+impl Context {
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    // Natives that touch a host resource call this first, like the builtins
+    // do.
+    pub fn check(&self, capability: Capability) -> Result<(), RuntimeError> {
+        self.capabilities.check(capability)
+    }
+}
+
+// A native that every interpreter defines as a global unless the prelude is
+// disabled.
+pub struct Builtin {
+    name: &'static str,
+    arity: usize,
+    signature: &'static str,
+    description: &'static str,
+    function: BuiltinFn,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn signature(&self) -> &'static str {
+        self.signature
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    pub(crate) fn native(&self, context: &Context) -> Rc<NativeFunction> {
+        let function = self.function;
+        let context = context.clone();
+        let native = move |arguments: &[Value]| function(&context, arguments);
+        Rc::new(NativeFunction::new(self.name, self.arity, Box::new(native)))
+    }
+}
+
+pub const BUILTINS: [Builtin; 9] = [
+    Builtin {
+        name: "clock",
+        arity: 0,
+        signature: "clock()",
+        description: "Seconds since the Unix epoch. Requires the clock capability.",
+        function: clock,
+    },
+    Builtin {
+        name: "type",
+        arity: 1,
+        signature: "type(x)",
+        description: "The name of the type of x, e.g. \"number\" or \"string\".",
+        function: type_of,
+    },
+    Builtin {
+        name: "str",
+        arity: 1,
+        signature: "str(x)",
+        description: "x converted to a string, as it would be printed.",
+        function: str,
+    },
+    Builtin {
+        name: "num",
+        arity: 1,
+        signature: "num(s)",
+        description: "The string s parsed as a number. Fails if s is not a number.",
+        function: num,
+    },
+    Builtin {
+        name: "len",
+        arity: 1,
+        signature: "len(x)",
+        description: "The number of characters in the string x, or of items in the list or map x.",
+        function: len,
+    },
+    Builtin {
+        name: "input",
+        arity: 0,
+        signature: "input()",
+        description: "Reads a line from standard input, or nil at the end of input. Requires the stdin capability.",
+        function: input,
+    },
+    Builtin {
+        name: "assert",
+        arity: 2,
+        signature: "assert(cond, msg)",
+        description: "Fails with msg unless cond is truthy.",
+        function: assert,
+    },
+    Builtin {
+        name: "panic",
+        arity: 1,
+        signature: "panic(msg)",
+        description: "Stops the program with msg as a runtime error.",
+        function: panic,
+    },
+    Builtin {
+        name: "gcStats",
+        arity: 0,
+        signature: "gcStats()",
+        description: "A map of the VM collector's statistics so far: collections, bytesAllocated, bytesFreed, peakHeap, and totalPause and maxPause in seconds.",
+        function: gc_stats,
+    },
+];
+
+fn clock(context: &Context, _: &[Value]) -> Result<Value, RuntimeError> {
+    context.capabilities.check(Capability::Clock)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(Value::Number(now.as_secs_f64()))
+}
+
+fn type_of(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::String(arguments[0].type_name().into()))
+}
+
+fn str(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::String(arguments[0].to_string().into()))
+}
+
+fn num(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Number(n) => Ok(Value::Number(*n)),
+        Value::String(s) => match s.trim().parse() {
+            Ok(n) => Ok(Value::Number(n)),
+            Err(_) => Err(InvalidNumber(s.to_string())),
+        },
+        value => Err(TypeMismatch("string", value.type_name())),
+    }
+}
+
+fn len(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        Value::List(items) => Ok(Value::Number(items.borrow().len() as f64)),
+        Value::Map(entries) => Ok(Value::Number(entries.borrow().len() as f64)),
+        value => Err(TypeMismatch("string, list or map", value.type_name())),
+    }
+}
+
+fn input(context: &Context, _: &[Value]) -> Result<Value, RuntimeError> {
+    context.capabilities.check(Capability::Stdin)?;
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) => Ok(Value::Nil),
+        Ok(_) => {
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            Ok(Value::String(line.into()))
+        }
+        Err(error) => Err(InputError(error)),
+    }
+}
+
+fn assert(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    if arguments[0].is_truthy() {
+        Ok(Value::Nil)
+    } else {
+        Err(AssertionFailed(arguments[1].to_string()))
+    }
+}
+
+fn panic(_: &Context, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    Err(Panic(arguments[0].to_string()))
+}
+
+fn gc_stats(context: &Context, _: &[Value]) -> Result<Value, RuntimeError> {
+    let stats = context.gc_stats.borrow();
+    let entries = [
+        ("collections", stats.collections as f64),
+        ("bytesAllocated", stats.bytes_allocated as f64),
+        ("bytesFreed", stats.bytes_freed as f64),
+        ("peakHeap", stats.peak_heap as f64),
+        ("totalPause", stats.total_pause.as_secs_f64()),
+        ("maxPause", stats.max_pause.as_secs_f64()),
+    ];
+    map::new(
+        entries
+            .into_iter()
+            .map(|(key, value)| (Value::String(key.into()), Value::Number(value)))
+            .collect(),
+    )
+}
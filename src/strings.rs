@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use crate::convert::FromLox;
+use crate::interpreter::RuntimeError;
+use crate::list;
+use crate::value::{NativeFunction, Value};
+
+type StringMethod = fn(&str, &[Value]) -> Result<Value, RuntimeError>;
+
+// Positions and lengths are counted in characters, not bytes.
+pub const METHODS: [(&str, usize, StringMethod); 10] = [
+    ("substring", 2, substring),
+    ("indexOf", 1, index_of),
+    ("trim", 0, trim),
+    ("upper", 0, upper),
+    ("lower", 0, lower),
+    ("replace", 2, replace),
+    ("startsWith", 1, starts_with),
+    ("endsWith", 1, ends_with),
+    ("len", 0, len),
+    ("split", 1, split),
+];
+
+// Looks up a method on a string, returning it bound to the string.
+pub fn method(receiver: Rc<str>, name: &str) -> Option<Value> {
+    let (name, arity, function) = METHODS.into_iter().find(|(method, _, _)| *method == name)?;
+    let bound = move |arguments: &[Value]| function(&receiver, arguments);
+    let native = NativeFunction::new(name, arity, Box::new(bound));
+    Some(Value::Native(Rc::new(native)))
+}
+
+pub fn char_at(s: &str, index: &Value) -> Result<Value, RuntimeError> {
+    let position = list::position(index, s.chars().count())?;
+    let c = s.chars().nth(position).unwrap_or_default();
+    Ok(string(c.encode_utf8(&mut [0; 4])))
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.into())
+}
+
+fn substring(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let (start, end) = list::bounds(&arguments[0], &arguments[1], s.chars().count())?;
+    let substring: String = s.chars().skip(start).take(end - start).collect();
+    Ok(string(&substring))
+}
+
+fn index_of(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let needle = String::from_lox(&arguments[0])?;
+    let index = match s.find(&needle) {
+        Some(byte_index) => s[..byte_index].chars().count() as f64,
+        None => -1.0,
+    };
+    Ok(Value::Number(index))
+}
+
+fn trim(s: &str, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(string(s.trim()))
+}
+
+fn upper(s: &str, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(string(&s.to_uppercase()))
+}
+
+fn lower(s: &str, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(string(&s.to_lowercase()))
+}
+
+fn replace(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let from = String::from_lox(&arguments[0])?;
+    let to = String::from_lox(&arguments[1])?;
+    Ok(string(&s.replace(&from, &to)))
+}
+
+fn starts_with(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let prefix = String::from_lox(&arguments[0])?;
+    Ok(Value::Boolean(s.starts_with(&prefix)))
+}
+
+fn ends_with(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let suffix = String::from_lox(&arguments[0])?;
+    Ok(Value::Boolean(s.ends_with(&suffix)))
+}
+
+fn len(s: &str, _: &[Value]) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(s.chars().count() as f64))
+}
+
+// An empty separator splits the string into its characters.
+fn split(s: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+    let separator = String::from_lox(&arguments[0])?;
+    let parts = if separator.is_empty() {
+        s.chars()
+            .map(|c| string(c.encode_utf8(&mut [0; 4])))
+            .collect()
+    } else {
+        s.split(&separator).map(string).collect()
+    };
+    Ok(list::new(parts))
+}
@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+
+// Interned strings are leaked and live for the rest of the program, which lets
+// symbols hand out `&'static str` without holding a borrow on the interner.
+// Only identifiers and the names of globals and classes the host defines are
+// interned. String values are reference counted instead, and names the host
+// merely looks up are never interned, so neither grows the interner.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+impl Symbol {
+    pub fn intern(string: &str) -> Symbol {
+        INTERNER.with(|interner| {
+            let mut interner = interner.borrow_mut();
+            if let Some(symbol) = interner.symbols.get(string) {
+                return *symbol;
+            }
+
+            let string: &'static str = Box::leak(string.into());
+            let symbol = Symbol(interner.strings.len() as u32);
+            interner.strings.push(string);
+            interner.symbols.insert(string, symbol);
+            symbol
+        })
+    }
+
+    // Finds the symbol for `string` without interning it. A name that was never
+    // interned can't name anything.
+    pub fn lookup(string: &str) -> Option<Symbol> {
+        INTERNER.with(|interner| interner.borrow().symbols.get(string).copied())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        INTERNER.with(|interner| interner.borrow().strings[self.0 as usize])
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
@@ -1,14 +1,19 @@
 use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::symbol::Symbol;
 
 #[derive(Clone, Debug, PartialEq)]
 #[allow(dead_code)]
-// TODO: Implement display trait
 pub enum TokenType {
     // Single-character tokens.
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
     Comma,
     Dot,
     Minus,
@@ -28,8 +33,10 @@ pub enum TokenType {
     LessEqual,
 
     // Literals.
-    Identifier(String),
-    String(String),
+    Identifier(Symbol),
+    String(Rc<str>),
+    // The part of an interpolated string before an embedded `${`.
+    Interpolation(Rc<str>),
     Number(f64),
 
     // Keywords.
@@ -53,6 +60,57 @@ pub enum TokenType {
     Eof,
 }
 
+// Shows the token as it appears in source.
+impl Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lexeme = match self {
+            TokenType::LeftParen => "(",
+            TokenType::RightParen => ")",
+            TokenType::LeftBrace => "{",
+            TokenType::RightBrace => "}",
+            TokenType::LeftBracket => "[",
+            TokenType::RightBracket => "]",
+            TokenType::Colon => ":",
+            TokenType::Comma => ",",
+            TokenType::Dot => ".",
+            TokenType::Minus => "-",
+            TokenType::Plus => "+",
+            TokenType::Semicolon => ";",
+            TokenType::Slash => "/",
+            TokenType::Star => "*",
+            TokenType::Bang => "!",
+            TokenType::BangEqual => "!=",
+            TokenType::Equal => "=",
+            TokenType::EqualEqual => "==",
+            TokenType::Greater => ">",
+            TokenType::GreaterEqual => ">=",
+            TokenType::Less => "<",
+            TokenType::LessEqual => "<=",
+            TokenType::Identifier(name) => return name.fmt(f),
+            TokenType::String(s) | TokenType::Interpolation(s) => return write!(f, "{:?}", s),
+            TokenType::Number(n) => return n.fmt(f),
+            TokenType::And => "and",
+            TokenType::Class => "class",
+            TokenType::Else => "else",
+            TokenType::False => "false",
+            TokenType::Fun => "fun",
+            TokenType::For => "for",
+            TokenType::If => "if",
+            TokenType::Nil => "nil",
+            TokenType::Or => "or",
+            TokenType::Print => "print",
+            TokenType::Return => "return",
+            TokenType::Super => "super",
+            TokenType::This => "this",
+            TokenType::True => "true",
+            TokenType::Var => "var",
+            TokenType::While => "while",
+            TokenType::Eof => "end of input",
+        };
+        f.write_str(lexeme)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     token_type: TokenType,
@@ -100,6 +158,10 @@ impl Span {
     pub fn new(line: usize) -> Span {
         Span { line }
     }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
 }
 
 impl Display for Token {
@@ -0,0 +1,369 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::ast::LiteralExpr;
+use crate::class::{BoundMethod, HostClass, Instance};
+use crate::depth;
+use crate::interpreter::RuntimeError;
+use crate::list::{self, List};
+use crate::map::{self, Map};
+use crate::strings;
+use crate::symbol::Symbol;
+use crate::token::{Token, TokenType};
+
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value, RuntimeError>;
+
+#[derive(Clone)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    Number(f64),
+    String(Rc<str>),
+    Native(Rc<NativeFunction>),
+    Class(Rc<HostClass>),
+    Instance(Rc<Instance>),
+    BoundMethod(Rc<BoundMethod>),
+    List(List),
+    Map(Map),
+}
+
+// How many arguments a callable accepts: `min` required ones, followed by up to
+// `max - min` optional ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl Arity {
+    pub fn exactly(count: usize) -> Arity {
+        Arity {
+            min: count,
+            max: count,
+        }
+    }
+
+    // The bound that `count` arguments violate, if any.
+    pub fn expected(&self, count: usize) -> Option<usize> {
+        if count < self.min {
+            Some(self.min)
+        } else if count > self.max {
+            Some(self.max)
+        } else {
+            None
+        }
+    }
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Boolean(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Native(_) | Value::BoundMethod(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Nil => false,
+            Value::Boolean(b) => *b,
+            _ => true,
+        }
+    }
+
+    pub fn is_equal(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Boolean(b1), Value::Boolean(b2)) => b1 == b2,
+            (Value::Number(n1), Value::Number(n2)) => (n1 - n2).abs() < 0.01,
+            (Value::String(s1), Value::String(s2)) => s1 == s2,
+            (Value::Native(f1), Value::Native(f2)) => Rc::ptr_eq(f1, f2),
+            (Value::Class(c1), Value::Class(c2)) => Rc::ptr_eq(c1, c2),
+            (Value::Instance(i1), Value::Instance(i2)) => Rc::ptr_eq(i1, i2),
+            (Value::BoundMethod(m1), Value::BoundMethod(m2)) => Rc::ptr_eq(m1, m2),
+            (Value::List(l1), Value::List(l2)) => Rc::ptr_eq(l1, l2),
+            (Value::Map(m1), Value::Map(m2)) => Rc::ptr_eq(m1, m2),
+            _ => false,
+        }
+    }
+
+    // The number of arguments a callable expects, or `None` if it can't be called.
+    pub fn arity(&self) -> Option<Arity> {
+        match self {
+            Value::Native(native) => Some(native.arity()),
+            Value::Class(class) => Some(Arity::exactly(class.arity())),
+            Value::BoundMethod(method) => Some(Arity::exactly(method.arity())),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn callee_name(&self) -> Symbol {
+        match self {
+            Value::Native(native) => native.name(),
+            Value::Class(class) => class.name(),
+            Value::BoundMethod(method) => method.name(),
+            _ => Symbol::intern("<value>"),
+        }
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        depth::nested(|| self.call_unchecked(arguments))
+    }
+
+    // Builtins can call back into values, so every call counts towards the
+    // nesting limit.
+    fn call_unchecked(&self, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        match self {
+            Value::Native(native) => {
+                check_arity(native.name(), native.arity(), arguments)?;
+                native.call(arguments)
+            }
+            Value::Class(class) => {
+                check_arity(class.name(), Arity::exactly(class.arity()), arguments)?;
+                class.construct(arguments)
+            }
+            Value::BoundMethod(method) => {
+                check_arity(method.name(), Arity::exactly(method.arity()), arguments)?;
+                method.call(arguments)
+            }
+            _ => Err(RuntimeError::TypeMismatch("function", self.type_name())),
+        }
+    }
+
+    pub fn get_property(&self, name: &Token) -> Result<Value, RuntimeError> {
+        match (self, name.token_type()) {
+            (Value::Instance(instance), TokenType::Identifier(property)) => instance
+                .get(*property)
+                .unwrap_or_else(|| Err(RuntimeError::UndefinedProperty(name.clone()))),
+            (
+                Value::String(_) | Value::List(_) | Value::Map(_),
+                TokenType::Identifier(property),
+            ) => self
+                .builtin_method(property.as_str())
+                .ok_or_else(|| RuntimeError::UndefinedProperty(name.clone())),
+            _ => Err(RuntimeError::NotAnInstance(name.clone())),
+        }
+    }
+
+    // Looks up a method of a string, list or map by name, bound to the value.
+    pub fn builtin_method(&self, name: &str) -> Option<Value> {
+        match self {
+            Value::String(s) => strings::method(Rc::clone(s), name),
+            Value::List(items) => list::method(items, name),
+            Value::Map(entries) => map::method(entries, name),
+            _ => None,
+        }
+    }
+
+    pub fn index(&self, index: &Value, bracket: &Token) -> Result<Value, RuntimeError> {
+        match self {
+            Value::String(s) => strings::char_at(s, index),
+            Value::List(items) => {
+                let items = items.borrow();
+                let position = list::position(index, items.len())?;
+                Ok(items[position].clone())
+            }
+            // Missing keys read as nil; `has` tells them apart from nil values.
+            Value::Map(entries) => {
+                let key = map::Key::new(index)?;
+                Ok(entries.borrow().get(&key).cloned().unwrap_or(Value::Nil))
+            }
+            _ => Err(RuntimeError::NotIndexable(bracket.clone())),
+        }
+    }
+
+    pub fn set_index(
+        &self,
+        index: &Value,
+        value: Value,
+        bracket: &Token,
+    ) -> Result<(), RuntimeError> {
+        match self {
+            Value::List(items) => {
+                let mut items = items.borrow_mut();
+                let position = list::position(index, items.len())?;
+                items[position] = value;
+                Ok(())
+            }
+            Value::Map(entries) => {
+                entries.borrow_mut().insert(map::Key::new(index)?, value);
+                Ok(())
+            }
+            _ => Err(RuntimeError::NotAssignable(bracket.clone())),
+        }
+    }
+
+    pub fn set_property(&self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        match (self, name.token_type()) {
+            (Value::Instance(instance), TokenType::Identifier(property)) => instance
+                .set(*property, value)
+                .unwrap_or_else(|| Err(RuntimeError::UndefinedProperty(name.clone()))),
+            _ => Err(RuntimeError::NotAnInstance(name.clone())),
+        }
+    }
+
+    pub fn to_literal(&self) -> Option<LiteralExpr> {
+        match self {
+            Value::Nil => Some(LiteralExpr::Nil()),
+            Value::Boolean(b) => Some(LiteralExpr::Boolean(*b)),
+            Value::Number(n) => Some(LiteralExpr::Number(*n)),
+            Value::String(s) => Some(LiteralExpr::String(Rc::clone(s))),
+            Value::Native(_)
+            | Value::Class(_)
+            | Value::Instance(_)
+            | Value::BoundMethod(_)
+            | Value::List(_)
+            | Value::Map(_) => None,
+        }
+    }
+}
+
+fn check_arity(name: Symbol, arity: Arity, arguments: &[Value]) -> Result<(), RuntimeError> {
+    match arity.expected(arguments.len()) {
+        Some(expected) => Err(RuntimeError::WrongArgumentCount(
+            name,
+            expected,
+            arguments.len(),
+        )),
+        None => Ok(()),
+    }
+}
+
+impl From<&LiteralExpr> for Value {
+    fn from(literal: &LiteralExpr) -> Value {
+        match literal {
+            LiteralExpr::Nil() => Value::Nil,
+            LiteralExpr::Boolean(b) => Value::Boolean(*b),
+            LiteralExpr::Number(n) => Value::Number(*n),
+            LiteralExpr::String(s) => Value::String(Rc::clone(s)),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Nested {
+            value: self,
+            parent: None,
+        }
+        .fmt(f)
+    }
+}
+
+// Lists and maps can contain themselves, so each nested value knows the
+// containers it is being displayed inside of.
+struct Nested<'a> {
+    value: &'a Value,
+    parent: Option<&'a Nested<'a>>,
+}
+
+impl Nested<'_> {
+    fn nested<'b>(&'b self, value: &'b Value) -> Nested<'b> {
+        Nested {
+            value,
+            parent: Some(self),
+        }
+    }
+
+    fn is_inside(&self, container: *const ()) -> bool {
+        let mut parent = self.parent;
+        while let Some(nested) = parent {
+            if container_ptr(nested.value) == Some(container) {
+                return true;
+            }
+            parent = nested.parent;
+        }
+        false
+    }
+}
+
+fn container_ptr(value: &Value) -> Option<*const ()> {
+    match value {
+        Value::List(items) => Some(Rc::as_ptr(items) as *const ()),
+        Value::Map(entries) => Some(Rc::as_ptr(entries) as *const ()),
+        _ => None,
+    }
+}
+
+impl Display for Nested<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Value::Nil => write!(f, "nil"),
+            Value::Boolean(b) => b.fmt(f),
+            Value::Number(n) => n.fmt(f),
+            Value::String(s) => s.fmt(f),
+            Value::Native(native) => write!(f, "<native fn {}>", native.name()),
+            Value::Class(class) => class.name().fmt(f),
+            Value::Instance(instance) => write!(f, "{} instance", instance.class().name()),
+            Value::BoundMethod(method) => write!(f, "<native fn {}>", method.name()),
+            Value::List(items) if self.is_inside(Rc::as_ptr(items) as *const ()) => {
+                write!(f, "[...]")
+            }
+            Value::Map(entries) if self.is_inside(Rc::as_ptr(entries) as *const ()) => {
+                write!(f, "{{...}}")
+            }
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    self.nested(item).fmt(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let key = key.value();
+                    write!(f, "{}: {}", self.nested(&key), self.nested(value))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+pub struct NativeFunction {
+    name: Symbol,
+    arity: Arity,
+    function: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: usize, function: Box<NativeFn>) -> NativeFunction {
+        NativeFunction {
+            name: Symbol::intern(name),
+            arity: Arity::exactly(arity),
+            function,
+        }
+    }
+
+    // Lets callers leave off up to `count` trailing arguments.
+    pub fn with_optional(mut self, count: usize) -> NativeFunction {
+        self.arity.max = self.arity.min + count;
+        self
+    }
+
+    pub fn name(&self) -> Symbol {
+        self.name
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    pub fn call(&self, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        (self.function)(arguments)
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use crate::vm::gc::ObjRef;
+use crate::vm::value::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum OpCode {
+    Constant,
+    GetGlobal,
+    GetProperty,
+    SetProperty,
+    Index,
+    SetIndex,
+    BuildList,
+    BuildMap,
+    Concat,
+    Nil,
+    True,
+    False,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    Call,
+    Return,
+}
+
+impl OpCode {
+    const ALL: [OpCode; 26] = [
+        OpCode::Constant,
+        OpCode::GetGlobal,
+        OpCode::GetProperty,
+        OpCode::SetProperty,
+        OpCode::Index,
+        OpCode::SetIndex,
+        OpCode::BuildList,
+        OpCode::BuildMap,
+        OpCode::Concat,
+        OpCode::Nil,
+        OpCode::True,
+        OpCode::False,
+        OpCode::Equal,
+        OpCode::NotEqual,
+        OpCode::Greater,
+        OpCode::GreaterEqual,
+        OpCode::Less,
+        OpCode::LessEqual,
+        OpCode::Add,
+        OpCode::Subtract,
+        OpCode::Multiply,
+        OpCode::Divide,
+        OpCode::Not,
+        OpCode::Negate,
+        OpCode::Call,
+        OpCode::Return,
+    ];
+
+    pub fn from_byte(byte: u8) -> Option<OpCode> {
+        OpCode::ALL.get(byte as usize).copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCode::Constant => "OP_CONSTANT",
+            OpCode::GetGlobal => "OP_GET_GLOBAL",
+            OpCode::GetProperty => "OP_GET_PROPERTY",
+            OpCode::SetProperty => "OP_SET_PROPERTY",
+            OpCode::Index => "OP_INDEX",
+            OpCode::SetIndex => "OP_SET_INDEX",
+            OpCode::BuildList => "OP_BUILD_LIST",
+            OpCode::BuildMap => "OP_BUILD_MAP",
+            OpCode::Concat => "OP_CONCAT",
+            OpCode::Nil => "OP_NIL",
+            OpCode::True => "OP_TRUE",
+            OpCode::False => "OP_FALSE",
+            OpCode::Equal => "OP_EQUAL",
+            OpCode::NotEqual => "OP_NOT_EQUAL",
+            OpCode::Greater => "OP_GREATER",
+            OpCode::GreaterEqual => "OP_GREATER_EQUAL",
+            OpCode::Less => "OP_LESS",
+            OpCode::LessEqual => "OP_LESS_EQUAL",
+            OpCode::Add => "OP_ADD",
+            OpCode::Subtract => "OP_SUBTRACT",
+            OpCode::Multiply => "OP_MULTIPLY",
+            OpCode::Divide => "OP_DIVIDE",
+            OpCode::Not => "OP_NOT",
+            OpCode::Negate => "OP_NEGATE",
+            OpCode::Call => "OP_CALL",
+            OpCode::Return => "OP_RETURN",
+        }
+    }
+}
+
+// Constants are numbers or interned strings, so equal constants have the same
+// bits or point to the same object.
+#[derive(PartialEq, Eq, Hash)]
+enum ConstantKey {
+    Number(u64),
+    Obj(ObjRef),
+}
+
+impl ConstantKey {
+    fn new(value: &Value) -> Option<ConstantKey> {
+        match (value.as_number(), value.as_obj()) {
+            (Some(n), _) => Some(ConstantKey::Number(n.to_bits())),
+            (_, Some(obj)) => Some(ConstantKey::Obj(obj)),
+            _ => None,
+        }
+    }
+}
+
+// Operands that index constants or count list and map items are two bytes,
+// most significant first. Argument counts fit in one.
+pub struct Chunk {
+    code: Vec<u8>,
+    constants: Vec<Value>,
+    constant_indices: HashMap<ConstantKey, usize>,
+    // Run-length encoded (line, instruction bytes on that line) pairs.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Chunk {
+    pub fn new() -> Chunk {
+        Chunk {
+            code: Vec::new(),
+            constants: Vec::new(),
+            constant_indices: HashMap::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn write(&mut self, byte: u8, line: usize) {
+        self.code.push(byte);
+
+        match self.lines.last_mut() {
+            Some((last_line, count)) if *last_line == line => *count += 1,
+            _ => self.lines.push((line, 1)),
+        }
+    }
+
+    pub fn write_op(&mut self, op: OpCode, line: usize) {
+        self.write(op as u8, line);
+    }
+
+    pub fn write_u16(&mut self, operand: u16, line: usize) {
+        for byte in operand.to_be_bytes() {
+            self.write(byte, line);
+        }
+    }
+
+    // Adding a constant that is already in the chunk returns its index.
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        let key = ConstantKey::new(&value);
+        if let Some(index) = key.as_ref().and_then(|key| self.constant_indices.get(key)) {
+            return *index;
+        }
+
+        self.constants.push(value);
+        let index = self.constants.len() - 1;
+        if let Some(key) = key {
+            self.constant_indices.insert(key, index);
+        }
+        index
+    }
+
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    pub fn read_u16(&self, offset: usize) -> u16 {
+        u16::from_be_bytes([self.code[offset], self.code[offset + 1]])
+    }
+
+    pub fn constant(&self, index: u16) -> &Value {
+        &self.constants[index as usize]
+    }
+
+    pub fn constants(&self) -> &[Value] {
+        &self.constants
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        let mut end = 0;
+        for (line, count) in &self.lines {
+            end += count;
+            if offset < end {
+                return *line;
+            }
+        }
+
+        self.lines.last().map_or(0, |(line, _)| *line)
+    }
+}
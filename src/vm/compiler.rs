@@ -0,0 +1,231 @@
+use std::fmt::{Display, Formatter};
+
+use crate::ast::{Expr, LiteralExpr};
+use crate::token::{Token, TokenType};
+use crate::vm::chunk::{Chunk, OpCode};
+use crate::vm::compiler::CompileError::{
+    TooManyArguments, TooManyConstants, TooManyElements, TooManyEntries, UnknownOperator,
+};
+use crate::vm::gc::Heap;
+use crate::vm::value::Value;
+
+#[derive(Debug)]
+pub enum CompileError {
+    UnknownOperator(Token),
+    TooManyConstants(usize),
+    TooManyArguments(usize),
+    TooManyElements(usize),
+    TooManyEntries(usize),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnknownOperator(token) => write!(f, "Unknown operator: {}", token),
+            TooManyConstants(line) => write!(f, "Too many constants in one chunk on line {}", line),
+            TooManyArguments(line) => write!(f, "Too many arguments in call on line {}", line),
+            TooManyElements(line) => {
+                write!(f, "Too many elements in list literal on line {}", line)
+            }
+            TooManyEntries(line) => write!(f, "Too many entries in map literal on line {}", line),
+        }
+    }
+}
+
+pub fn compile(expr: &Expr, heap: &mut Heap) -> Result<Chunk, CompileError> {
+    let mut compiler = Compiler::new(heap);
+    compiler.expression(expr)?;
+    compiler.emit(OpCode::Return);
+    Ok(compiler.chunk)
+}
+
+struct Compiler<'a> {
+    chunk: Chunk,
+    heap: &'a mut Heap,
+    // Literals carry no position, so they are attributed to the line of the
+    // nearest enclosing operator.
+    line: usize,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(heap: &'a mut Heap) -> Compiler<'a> {
+        Compiler {
+            chunk: Chunk::new(),
+            heap,
+            line: 1,
+        }
+    }
+
+    fn emit(&mut self, op: OpCode) {
+        self.chunk.write_op(op, self.line);
+    }
+
+    fn emit_constant(&mut self, value: Value) -> Result<(), CompileError> {
+        self.emit_with_constant(OpCode::Constant, value)
+    }
+
+    fn emit_with_constant(&mut self, op: OpCode, value: Value) -> Result<(), CompileError> {
+        let index = self.chunk.add_constant(value);
+        let index = u16::try_from(index).map_err(|_| TooManyConstants(self.line))?;
+        self.emit(op);
+        self.chunk.write_u16(index, self.line);
+        Ok(())
+    }
+
+    fn emit_property(&mut self, op: OpCode, name: &Token) -> Result<(), CompileError> {
+        match name.token_type() {
+            TokenType::Identifier(name) => {
+                let name = self.heap.intern(name.as_str());
+                self.emit_with_constant(op, Value::obj(name))
+            }
+            _ => Err(UnknownOperator(name.clone())),
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<(), CompileError> {
+        match expr {
+            Expr::Binary(b) => {
+                self.line = b.op().span().line;
+                self.expression(b.left())?;
+                self.expression(b.right())?;
+                self.line = b.op().span().line;
+
+                let op = match b.op().token_type() {
+                    TokenType::Plus => OpCode::Add,
+                    TokenType::Minus => OpCode::Subtract,
+                    TokenType::Star => OpCode::Multiply,
+                    TokenType::Slash => OpCode::Divide,
+                    TokenType::Greater => OpCode::Greater,
+                    TokenType::GreaterEqual => OpCode::GreaterEqual,
+                    TokenType::Less => OpCode::Less,
+                    TokenType::LessEqual => OpCode::LessEqual,
+                    TokenType::EqualEqual => OpCode::Equal,
+                    TokenType::BangEqual => OpCode::NotEqual,
+                    _ => return Err(UnknownOperator(b.op().clone())),
+                };
+                self.emit(op);
+                Ok(())
+            }
+            Expr::Call(c) => {
+                self.expression(c.callee())?;
+                for argument in c.arguments() {
+                    self.expression(argument)?;
+                }
+
+                self.line = c.paren().span().line;
+                let arg_count =
+                    u8::try_from(c.arguments().len()).map_err(|_| TooManyArguments(self.line))?;
+                self.emit(OpCode::Call);
+                self.chunk.write(arg_count, self.line);
+                Ok(())
+            }
+            Expr::Get(g) => {
+                self.expression(g.object())?;
+                self.line = g.name().span().line;
+                self.emit_property(OpCode::GetProperty, g.name())
+            }
+            Expr::Grouping(g) => self.expression(g.expression()),
+            Expr::Index(i) => {
+                self.expression(i.object())?;
+                self.expression(i.index())?;
+                self.line = i.bracket().span().line;
+                self.emit(OpCode::Index);
+                Ok(())
+            }
+            // Each part is appended to an initially empty string, so that even a
+            // lone embedded value is converted.
+            Expr::Interpolation(i) => {
+                self.line = i.token().span().line;
+                let empty = self.heap.intern("");
+                self.emit_constant(Value::obj(empty))?;
+                for part in i.parts() {
+                    self.expression(part)?;
+                    self.line = i.token().span().line;
+                    self.emit(OpCode::Concat);
+                }
+                Ok(())
+            }
+            Expr::List(l) => {
+                for element in l.elements() {
+                    self.expression(element)?;
+                }
+
+                self.line = l.bracket().span().line;
+                let count =
+                    u16::try_from(l.elements().len()).map_err(|_| TooManyElements(self.line))?;
+                self.emit(OpCode::BuildList);
+                self.chunk.write_u16(count, self.line);
+                Ok(())
+            }
+            Expr::Map(m) => {
+                for (key, value) in m.entries() {
+                    self.expression(key)?;
+                    self.expression(value)?;
+                }
+
+                self.line = m.brace().span().line;
+                let count =
+                    u16::try_from(m.entries().len()).map_err(|_| TooManyEntries(self.line))?;
+                self.emit(OpCode::BuildMap);
+                self.chunk.write_u16(count, self.line);
+                Ok(())
+            }
+            Expr::Literal(l) => match l {
+                LiteralExpr::Number(n) => self.emit_constant(Value::number(*n)),
+                LiteralExpr::String(s) => {
+                    let string = self.heap.intern(s);
+                    self.emit_constant(Value::obj(string))
+                }
+                LiteralExpr::Boolean(true) => {
+                    self.emit(OpCode::True);
+                    Ok(())
+                }
+                LiteralExpr::Boolean(false) => {
+                    self.emit(OpCode::False);
+                    Ok(())
+                }
+                LiteralExpr::Nil() => {
+                    self.emit(OpCode::Nil);
+                    Ok(())
+                }
+            },
+            Expr::Set(s) => {
+                self.expression(s.object())?;
+                self.expression(s.value())?;
+                self.line = s.name().span().line;
+                self.emit_property(OpCode::SetProperty, s.name())
+            }
+            Expr::SetIndex(s) => {
+                self.expression(s.object())?;
+                self.expression(s.index())?;
+                self.expression(s.value())?;
+                self.line = s.bracket().span().line;
+                self.emit(OpCode::SetIndex);
+                Ok(())
+            }
+            Expr::Unary(u) => {
+                self.line = u.op().span().line;
+                self.expression(u.expr())?;
+                self.line = u.op().span().line;
+
+                let op = match u.op().token_type() {
+                    TokenType::Minus => OpCode::Negate,
+                    TokenType::Bang => OpCode::Not,
+                    _ => return Err(UnknownOperator(u.op().clone())),
+                };
+                self.emit(op);
+                Ok(())
+            }
+            Expr::Variable(v) => {
+                self.line = v.name().span().line;
+                match v.name().token_type() {
+                    TokenType::Identifier(name) => {
+                        let name = self.heap.intern(name.as_str());
+                        self.emit_with_constant(OpCode::GetGlobal, Value::obj(name))
+                    }
+                    _ => Err(UnknownOperator(v.name().clone())),
+                }
+            }
+        }
+    }
+}
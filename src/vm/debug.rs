@@ -0,0 +1,78 @@
+use std::io::{self, Write};
+
+use crate::vm::chunk::{Chunk, OpCode};
+use crate::vm::gc::Heap;
+use crate::vm::value::Value;
+
+pub fn disassemble_chunk(
+    out: &mut dyn Write,
+    chunk: &Chunk,
+    heap: &Heap,
+    name: &str,
+) -> io::Result<()> {
+    writeln!(out, "== {} ==", name)?;
+
+    let mut offset = 0;
+    while offset < chunk.code().len() {
+        offset = disassemble_instruction(out, chunk, heap, offset)?;
+    }
+
+    Ok(())
+}
+
+pub fn disassemble_instruction(
+    out: &mut dyn Write,
+    chunk: &Chunk,
+    heap: &Heap,
+    offset: usize,
+) -> io::Result<usize> {
+    write!(out, "{:04} ", offset)?;
+    if offset > 0 && chunk.line(offset) == chunk.line(offset - 1) {
+        write!(out, "   | ")?;
+    } else {
+        write!(out, "{:4} ", chunk.line(offset))?;
+    }
+
+    let byte = chunk.code()[offset];
+    match OpCode::from_byte(byte) {
+        Some(
+            op @ (OpCode::Constant | OpCode::GetGlobal | OpCode::GetProperty | OpCode::SetProperty),
+        ) => {
+            let index = chunk.read_u16(offset + 1);
+            writeln!(
+                out,
+                "{:<16} {:4} '{}'",
+                op.name(),
+                index,
+                chunk.constant(index).display(heap)
+            )?;
+            Ok(offset + 3)
+        }
+        Some(op @ (OpCode::BuildList | OpCode::BuildMap)) => {
+            let count = chunk.read_u16(offset + 1);
+            writeln!(out, "{:<16} {:4}", op.name(), count)?;
+            Ok(offset + 3)
+        }
+        Some(OpCode::Call) => {
+            let count = chunk.code()[offset + 1];
+            writeln!(out, "{:<16} {:4}", OpCode::Call.name(), count)?;
+            Ok(offset + 2)
+        }
+        Some(op) => {
+            writeln!(out, "{}", op.name())?;
+            Ok(offset + 1)
+        }
+        None => {
+            writeln!(out, "Unknown opcode {}", byte)?;
+            Ok(offset + 1)
+        }
+    }
+}
+
+pub fn print_stack(out: &mut dyn Write, stack: &[Value], heap: &Heap) -> io::Result<()> {
+    write!(out, "          ")?;
+    for value in stack {
+        write!(out, "[ {} ]", value.display(heap))?;
+    }
+    writeln!(out)
+}
@@ -0,0 +1,321 @@
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::mem;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::class::{BoundMethod, HostClass, Instance};
+use crate::list::List;
+use crate::map::{Key, Map};
+use crate::value::{NativeFunction, Value as HostValue};
+use crate::vm::value::Value;
+
+const INITIAL_GC_THRESHOLD: usize = 1024 * 1024;
+const HEAP_GROW_FACTOR: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObjRef(u32);
+
+#[cfg(feature = "nan-boxing")]
+impl ObjRef {
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+
+    pub fn from_index(index: u32) -> ObjRef {
+        ObjRef(index)
+    }
+}
+
+pub enum Obj {
+    String(Rc<str>),
+    Native(Rc<NativeFunction>),
+    Class(Rc<HostClass>),
+    Instance(Rc<Instance>),
+    BoundMethod(Rc<BoundMethod>),
+    List(List),
+    Map(Map),
+    // A method of a list or map, along with its receiver.
+    Method(Value, Rc<NativeFunction>),
+}
+
+impl Obj {
+    // Objects shared with the host are identified by the address they point to.
+    fn host_ptr(&self) -> Option<*const ()> {
+        match self {
+            Obj::String(_) | Obj::Method(..) => None,
+            Obj::Native(native) => Some(Rc::as_ptr(native) as *const ()),
+            Obj::Class(class) => Some(Rc::as_ptr(class) as *const ()),
+            Obj::Instance(instance) => Some(Rc::as_ptr(instance) as *const ()),
+            Obj::BoundMethod(method) => Some(Rc::as_ptr(method) as *const ()),
+            Obj::List(list) => Some(Rc::as_ptr(list) as *const ()),
+            Obj::Map(map) => Some(Rc::as_ptr(map) as *const ()),
+        }
+    }
+
+    // Lists and maps change size as they are mutated, so the heap is told to
+    // measure them again after every change.
+    pub fn size(&self) -> usize {
+        let payload = match self {
+            Obj::String(s) => s.len(),
+            Obj::List(list) => list.borrow().len() * mem::size_of::<HostValue>(),
+            Obj::Map(map) => {
+                let entry = mem::size_of::<(Key, HostValue)>() + mem::size_of::<(Key, usize)>();
+                map.borrow().len() * entry
+            }
+            Obj::Instance(instance) => instance.size(),
+            // Natives and classes are set up by the host, not built by scripts.
+            Obj::Native(_) | Obj::Class(_) | Obj::BoundMethod(_) | Obj::Method(..) => 0,
+        };
+        mem::size_of::<HeapEntry>() + payload
+    }
+}
+
+struct HeapEntry {
+    obj: Obj,
+    size: usize,
+    marked: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GcStats {
+    pub collections: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+    pub peak_heap: usize,
+    pub total_pause: Duration,
+    pub max_pause: Duration,
+}
+
+impl Display for GcStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "collections:     {}", self.collections)?;
+        writeln!(f, "bytes allocated: {}", self.bytes_allocated)?;
+        writeln!(f, "bytes freed:     {}", self.bytes_freed)?;
+        writeln!(f, "peak heap:       {}", self.peak_heap)?;
+        writeln!(f, "total pause:     {:?}", self.total_pause)?;
+        write!(f, "max pause:       {:?}", self.max_pause)
+    }
+}
+
+// Objects start out white (unmarked), turn gray when marked and pushed on the
+// worklist, and black once their references have been traced.
+pub struct Heap {
+    objects: Vec<Option<HeapEntry>>,
+    // Every live string is interned here, so equal strings share one object.
+    // Entries are weak: sweeping a string removes it from the table.
+    strings: HashMap<Rc<str>, ObjRef>,
+    // Likewise each host object gets a single heap object, keyed by its address.
+    host_objects: HashMap<*const (), ObjRef>,
+    free: Vec<u32>,
+    gray: Vec<ObjRef>,
+    bytes_allocated: usize,
+    next_gc: usize,
+    stress: bool,
+    max_heap: Option<usize>,
+    // Shared with the `gcStats` builtin.
+    stats: Rc<RefCell<GcStats>>,
+}
+
+impl Heap {
+    pub fn new(stress: bool, max_heap: Option<usize>) -> Heap {
+        Heap {
+            objects: Vec::new(),
+            strings: HashMap::new(),
+            host_objects: HashMap::new(),
+            free: Vec::new(),
+            gray: Vec::new(),
+            bytes_allocated: 0,
+            next_gc: INITIAL_GC_THRESHOLD,
+            stress,
+            max_heap,
+            stats: Rc::default(),
+        }
+    }
+
+    pub fn stats(&self) -> Ref<'_, GcStats> {
+        self.stats.borrow()
+    }
+
+    pub fn stats_handle(&self) -> Rc<RefCell<GcStats>> {
+        Rc::clone(&self.stats)
+    }
+
+    pub fn max_heap(&self) -> Option<usize> {
+        self.max_heap
+    }
+
+    pub fn should_collect(&self, size: usize) -> bool {
+        self.stress || self.bytes_allocated + size > self.next_gc || self.exceeds_limit(size)
+    }
+
+    pub fn exceeds_limit(&self, size: usize) -> bool {
+        self.max_heap
+            .is_some_and(|max_heap| self.bytes_allocated + size > max_heap)
+    }
+
+    pub fn alloc(&mut self, obj: Obj) -> ObjRef {
+        let size = obj.size();
+        self.grow(size);
+        let entry = Some(HeapEntry {
+            obj,
+            size,
+            marked: false,
+        });
+
+        let obj = if let Some(index) = self.free.pop() {
+            self.objects[index as usize] = entry;
+            ObjRef(index)
+        } else {
+            self.objects.push(entry);
+            ObjRef((self.objects.len() - 1) as u32)
+        };
+
+        let entry = self.get(obj);
+        if let Obj::String(s) = entry {
+            let string = Rc::clone(s);
+            self.strings.insert(string, obj);
+        } else if let Some(ptr) = entry.host_ptr() {
+            self.host_objects.insert(ptr, obj);
+        }
+        obj
+    }
+
+    // Measures an object again after it was mutated.
+    pub fn resize(&mut self, obj: ObjRef) {
+        let Some(entry) = &mut self.objects[obj.0 as usize] else {
+            panic!("Error: Use of collected object {:?}", obj);
+        };
+        let (old, new) = (entry.size, entry.obj.size());
+        entry.size = new;
+
+        if new > old {
+            self.grow(new - old);
+        } else {
+            self.shrink(old - new);
+        }
+    }
+
+    fn grow(&mut self, size: usize) {
+        self.bytes_allocated += size;
+        let mut stats = self.stats.borrow_mut();
+        stats.bytes_allocated += size;
+        stats.peak_heap = stats.peak_heap.max(self.bytes_allocated);
+    }
+
+    fn shrink(&mut self, size: usize) {
+        self.bytes_allocated -= size;
+        self.stats.borrow_mut().bytes_freed += size;
+    }
+
+    pub fn find_string(&self, s: &str) -> Option<ObjRef> {
+        self.strings.get(s).copied()
+    }
+
+    pub fn find_host(&self, obj: &Obj) -> Option<ObjRef> {
+        self.host_objects.get(&obj.host_ptr()?).copied()
+    }
+
+    pub fn intern(&mut self, s: &str) -> ObjRef {
+        match self.find_string(s) {
+            Some(obj) => obj,
+            None => self.alloc(Obj::String(s.into())),
+        }
+    }
+
+    pub fn get(&self, obj: ObjRef) -> &Obj {
+        match &self.objects[obj.0 as usize] {
+            Some(entry) => &entry.obj,
+            None => panic!("Error: Use of collected object {:?}", obj),
+        }
+    }
+
+    pub fn as_str(&self, obj: ObjRef) -> Option<&str> {
+        match self.get(obj) {
+            Obj::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn collect(&mut self, roots: impl Iterator<Item = Value>) {
+        let start = Instant::now();
+
+        for root in roots {
+            self.mark_value(root);
+        }
+        self.trace_references();
+        self.sweep();
+
+        self.next_gc = (self.bytes_allocated * HEAP_GROW_FACTOR).max(INITIAL_GC_THRESHOLD);
+
+        let pause = start.elapsed();
+        let mut stats = self.stats.borrow_mut();
+        stats.collections += 1;
+        stats.total_pause += pause;
+        stats.max_pause = stats.max_pause.max(pause);
+    }
+
+    fn mark_value(&mut self, value: Value) {
+        if let Some(obj) = value.as_obj() {
+            self.mark_object(obj);
+        }
+    }
+
+    fn mark_object(&mut self, obj: ObjRef) {
+        if let Some(entry) = &mut self.objects[obj.0 as usize] {
+            if !entry.marked {
+                entry.marked = true;
+                self.gray.push(obj);
+            }
+        }
+    }
+
+    fn trace_references(&mut self) {
+        while let Some(obj) = self.gray.pop() {
+            self.blacken(obj);
+        }
+    }
+
+    fn blacken(&mut self, obj: ObjRef) {
+        // Host objects, lists and maps included, only hold host values, which
+        // keep what they refer to alive independently of the heap. Instance
+        // data in particular is opaque, so there is nothing in it to trace.
+        if let Obj::Method(receiver, _) = self.get(obj) {
+            let receiver = *receiver;
+            self.mark_value(receiver);
+        }
+    }
+
+    // Host code can change lists and maps without the heap noticing, so every
+    // surviving object is measured again.
+    fn sweep(&mut self) {
+        let (mut grown, mut shrunk) = (0, 0);
+        for (index, slot) in self.objects.iter_mut().enumerate() {
+            match slot {
+                Some(entry) if entry.marked => {
+                    entry.marked = false;
+                    let size = entry.obj.size();
+                    grown += size.saturating_sub(entry.size);
+                    shrunk += entry.size.saturating_sub(size);
+                    entry.size = size;
+                }
+                Some(entry) => {
+                    if let Obj::String(s) = &entry.obj {
+                        self.strings.remove(s);
+                    } else if let Some(ptr) = entry.obj.host_ptr() {
+                        self.host_objects.remove(&ptr);
+                    }
+
+                    self.bytes_allocated -= entry.size;
+                    self.stats.borrow_mut().bytes_freed += entry.size;
+                    *slot = None;
+                    self.free.push(index as u32);
+                }
+                None => {}
+            }
+        }
+        self.grow(grown);
+        self.shrink(shrunk);
+    }
+}
@@ -0,0 +1,525 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::budget::Budget;
+use crate::config::Config;
+use crate::depth;
+use crate::error::Error;
+use crate::interpreter::RuntimeError;
+use crate::interpreter::RuntimeError::{
+    ExpectedNumberLiterals, InvalidLiteralForUnary, InvalidOperandError, NotCallable, OutOfMemory,
+    TypeMismatch, UndefinedVariable, WrongArity,
+};
+use crate::map::{Key, Table};
+use crate::symbol::Symbol;
+use crate::token::{Span, Token, TokenType};
+use crate::value::{Arity, Value as HostValue};
+use crate::vm::chunk::{Chunk, OpCode};
+use crate::vm::debug::{disassemble_instruction, print_stack};
+use crate::vm::gc::{Heap, Obj, ObjRef};
+use crate::vm::value::Value;
+
+pub mod chunk;
+pub mod compiler;
+pub mod debug;
+pub mod gc;
+pub mod value;
+
+// Lists and maps are host objects that the heap holds on to, like instances.
+// Host code is handed the very list or map a script sees, so changes made on
+// either side show up on the other.
+pub struct Vm {
+    stack: Vec<Value>,
+    // The constants of the running chunk, or `None` outside of a run.
+    constants: Option<Vec<Value>>,
+    heap: Heap,
+    globals: HashMap<ObjRef, Value>,
+    budget: Budget,
+    max_depth: usize,
+    trace: bool,
+}
+
+impl Vm {
+    pub fn new(config: &Config) -> Vm {
+        Vm {
+            stack: Vec::new(),
+            constants: None,
+            heap: Heap::new(config.gc_stress, config.max_heap),
+            globals: HashMap::new(),
+            budget: Budget::new(config),
+            max_depth: config.max_call_depth,
+            trace: config.trace,
+        }
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    pub fn define_global(&mut self, name: Symbol, value: HostValue) {
+        let name = self.heap.intern(name.as_str());
+        let value = self
+            .import_value(value)
+            .expect("Error: Allocation failed outside of a run");
+        self.globals.insert(name, value);
+    }
+
+    pub fn global(&self, name: Symbol) -> Option<HostValue> {
+        let name = self.heap.find_string(name.as_str())?;
+        let value = *self.globals.get(&name)?;
+        Some(self.export(value))
+    }
+
+    // Trace output, when enabled, is written to `out`.
+    pub fn execute(&mut self, chunk: &Chunk, out: &mut dyn Write) -> Result<HostValue, Error> {
+        let value = self.run(chunk, out)?;
+        Ok(self.export(value))
+    }
+
+    pub fn run(&mut self, chunk: &Chunk, out: &mut dyn Write) -> Result<Value, Error> {
+        self.stack.clear();
+        self.constants = Some(chunk.constants().to_vec());
+        self.budget.start();
+
+        let result = depth::limit(self.max_depth, || self.run_chunk(chunk, out));
+        self.constants = None;
+        result
+    }
+
+    fn run_chunk(&mut self, chunk: &Chunk, out: &mut dyn Write) -> Result<Value, Error> {
+        let mut ip = 0;
+
+        loop {
+            let offset = ip;
+            if self.trace {
+                print_stack(out, &self.stack, &self.heap).map_err(Error::Write)?;
+                disassemble_instruction(out, chunk, &self.heap, offset).map_err(Error::Write)?;
+            }
+
+            self.budget.step()?;
+            let op = OpCode::from_byte(chunk.code()[ip]).expect("Error: Invalid opcode");
+            ip += 1;
+
+            match op {
+                OpCode::Constant => {
+                    let index = chunk.read_u16(ip);
+                    ip += 2;
+                    self.push(*chunk.constant(index));
+                }
+                OpCode::GetGlobal => {
+                    let name = *chunk.constant(chunk.read_u16(ip));
+                    ip += 2;
+
+                    let global = name.as_obj().and_then(|name| self.globals.get(&name));
+                    match global {
+                        Some(value) => self.push(*value),
+                        None => {
+                            let name = self.as_string(name).unwrap_or_default();
+                            let token = Token::new(
+                                TokenType::Identifier(Symbol::intern(name)),
+                                Span::new(chunk.line(offset)),
+                            );
+                            return Err(UndefinedVariable(token).into());
+                        }
+                    }
+                }
+                OpCode::GetProperty => {
+                    let name = self.property_name(chunk, offset, chunk.read_u16(ip));
+                    ip += 2;
+
+                    // Keep the object rooted until the property is on the heap.
+                    let value = self.get_property(self.peek(0), &name)?;
+                    self.pop();
+                    self.push(value);
+                }
+                OpCode::SetProperty => {
+                    let name = self.property_name(chunk, offset, chunk.read_u16(ip));
+                    ip += 2;
+
+                    let (object, value) = (self.peek(1), self.peek(0));
+                    self.set_property(object, &name, value)?;
+                    self.pop_pair();
+                    self.push(value);
+                }
+                OpCode::Index => {
+                    let line = chunk.line(offset);
+                    let bracket = Token::new(TokenType::RightBracket, Span::new(line));
+
+                    // Keep the operands rooted until the element is on the heap.
+                    let value = self.index(self.peek(1), self.peek(0), &bracket)?;
+                    self.pop_pair();
+                    self.push(value);
+                }
+                OpCode::SetIndex => {
+                    let line = chunk.line(offset);
+                    let bracket = Token::new(TokenType::RightBracket, Span::new(line));
+                    let value = self.pop();
+                    let index = self.pop();
+                    let object = self.pop();
+                    self.set_index(object, index, value, &bracket)?;
+                    self.push(value);
+                }
+                OpCode::BuildList => {
+                    let count = chunk.read_u16(ip) as usize;
+                    ip += 2;
+
+                    let items = self.stack[self.stack.len() - count..]
+                        .iter()
+                        .map(|item| self.export(*item))
+                        .collect();
+
+                    // Keep the items rooted until the list is on the heap.
+                    let value = self.allocate_value(Obj::List(Rc::new(RefCell::new(items))))?;
+                    self.stack.truncate(self.stack.len() - count);
+                    self.push(value);
+                }
+                OpCode::BuildMap => {
+                    let count = chunk.read_u16(ip) as usize;
+                    ip += 2;
+
+                    let mut entries = Table::default();
+                    for entry in self.stack[self.stack.len() - 2 * count..].chunks(2) {
+                        let key = Key::new(&self.export(entry[0]))?;
+                        entries.insert(key, self.export(entry[1]));
+                    }
+
+                    // Keep the entries rooted until the map is on the heap.
+                    let value = self.allocate_value(Obj::Map(Rc::new(RefCell::new(entries))))?;
+                    self.stack.truncate(self.stack.len() - 2 * count);
+                    self.push(value);
+                }
+                OpCode::Concat => {
+                    let (left, right) = (self.peek(1), self.peek(0));
+                    let string =
+                        format!("{}{}", left.display(&self.heap), right.display(&self.heap));
+
+                    // Keep the operands rooted until the result is on the heap.
+                    let value = self.allocate_string(string)?;
+                    self.pop_pair();
+                    self.push(value);
+                }
+                OpCode::Nil => self.push(Value::nil()),
+                OpCode::True => self.push(Value::boolean(true)),
+                OpCode::False => self.push(Value::boolean(false)),
+                OpCode::Equal => {
+                    let (left, right) = self.pop_pair();
+                    self.push(Value::boolean(left.is_equal(&right)));
+                }
+                OpCode::NotEqual => {
+                    let (left, right) = self.pop_pair();
+                    self.push(Value::boolean(!left.is_equal(&right)));
+                }
+                OpCode::Greater => self.number_op(">", |n1, n2| Value::boolean(n1 > n2))?,
+                OpCode::GreaterEqual => self.number_op(">=", |n1, n2| Value::boolean(n1 >= n2))?,
+                OpCode::Less => self.number_op("<", |n1, n2| Value::boolean(n1 < n2))?,
+                OpCode::LessEqual => self.number_op("<=", |n1, n2| Value::boolean(n1 <= n2))?,
+                OpCode::Add => {
+                    let (left, right) = (self.peek(1), self.peek(0));
+                    let value = match (left.as_number(), right.as_number()) {
+                        (Some(n1), Some(n2)) => Value::number(n1 + n2),
+                        _ => match (self.as_string(left), self.as_string(right)) {
+                            (Some(s1), Some(s2)) => {
+                                let string = format!("{}{}", s1, s2);
+                                self.allocate_string(string)?
+                            }
+                            _ => {
+                                let line = chunk.line(offset);
+                                let token = Token::new(TokenType::Plus, Span::new(line));
+                                return Err(InvalidOperandError(token).into());
+                            }
+                        },
+                    };
+                    self.pop_pair();
+                    self.push(value);
+                }
+                OpCode::Subtract => self.number_op("-", |n1, n2| Value::number(n1 - n2))?,
+                OpCode::Multiply => self.number_op("*", |n1, n2| Value::number(n1 * n2))?,
+                OpCode::Divide => self.number_op("/", |n1, n2| Value::number(n1 / n2))?,
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.push(Value::boolean(!value.is_truthy()));
+                }
+                OpCode::Negate => {
+                    if let Some(n) = self.pop().as_number() {
+                        self.push(Value::number(-n));
+                    } else {
+                        let token = Token::new(TokenType::Minus, Span::new(chunk.line(offset)));
+                        return Err(InvalidLiteralForUnary(token).into());
+                    }
+                }
+                OpCode::Call => {
+                    let arg_count = chunk.code()[ip] as usize;
+                    ip += 1;
+
+                    let paren = Token::new(TokenType::RightParen, Span::new(chunk.line(offset)));
+                    let callee = self.peek(arg_count);
+                    match self.arity(callee).map(|arity| arity.expected(arg_count)) {
+                        Some(Some(expected)) => {
+                            return Err(WrongArity(paren, expected, arg_count).into());
+                        }
+                        Some(None) => {}
+                        None => return Err(NotCallable(paren).into()),
+                    }
+
+                    // Keep the callee and arguments rooted until the result is
+                    // on the heap.
+                    let value = self.invoke(callee, arg_count).map_err(|error| {
+                        error.in_call(self.callee_name(callee), Some(chunk.line(offset)))
+                    })?;
+                    self.stack.truncate(self.stack.len() - 1 - arg_count);
+                    self.push(value);
+                }
+                OpCode::Return => return Ok(self.pop()),
+            }
+        }
+    }
+
+    fn arity(&self, callee: Value) -> Option<Arity> {
+        match self.heap.get(callee.as_obj()?) {
+            Obj::Native(native) | Obj::Method(_, native) => Some(native.arity()),
+            Obj::Class(class) => Some(Arity::exactly(class.arity())),
+            Obj::BoundMethod(method) => Some(Arity::exactly(method.arity())),
+            _ => None,
+        }
+    }
+
+    fn callee_name(&self, callee: Value) -> Symbol {
+        match callee.as_obj().map(|obj| self.heap.get(obj)) {
+            Some(Obj::Native(native) | Obj::Method(_, native)) => native.name(),
+            Some(Obj::Class(class)) => class.name(),
+            Some(Obj::BoundMethod(method)) => method.name(),
+            _ => Symbol::intern("<value>"),
+        }
+    }
+
+    // Calls `callee` with the `arg_count` values on top of the stack. The
+    // caller has already checked the arity.
+    fn invoke(&mut self, callee: Value, arg_count: usize) -> Result<Value, RuntimeError> {
+        let arguments = self.stack[self.stack.len() - arg_count..].to_vec();
+        let Some(obj) = callee.as_obj() else {
+            return Err(TypeMismatch("function", callee.type_name(&self.heap)));
+        };
+
+        let host_arguments: Vec<HostValue> = arguments.iter().map(|a| self.export(*a)).collect();
+        let result = depth::nested(|| match self.heap.get(obj) {
+            Obj::Native(native) | Obj::Method(_, native) => native.call(&host_arguments),
+            Obj::Class(class) => class.construct(&host_arguments),
+            Obj::BoundMethod(method) => method.call(&host_arguments),
+            _ => Err(TypeMismatch("function", callee.type_name(&self.heap))),
+        });
+        let receiver = match self.heap.get(obj) {
+            Obj::Method(receiver, _) => Some(*receiver),
+            _ => None,
+        };
+
+        // Host code may have changed the lists and maps it was given.
+        for value in arguments.into_iter().chain(receiver) {
+            if let Some(obj) = value.as_obj() {
+                self.resize(obj)?;
+            }
+        }
+        self.import_value(result?)
+    }
+
+    fn get_property(&mut self, object: Value, name: &Token) -> Result<Value, RuntimeError> {
+        let property = self.export(object).get_property(name)?;
+        match property {
+            // Methods of lists and maps keep their receiver, so that it can be
+            // measured again after each call.
+            HostValue::Native(method) if self.is_container(object) => {
+                self.allocate_value(Obj::Method(object, method))
+            }
+            property => self.import_value(property),
+        }
+    }
+
+    fn set_property(
+        &mut self,
+        object: Value,
+        name: &Token,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        self.export(object).set_property(name, self.export(value))?;
+        self.resize_value(object)
+    }
+
+    fn index(
+        &mut self,
+        object: Value,
+        index: Value,
+        bracket: &Token,
+    ) -> Result<Value, RuntimeError> {
+        let element = self.export(object).index(&self.export(index), bracket)?;
+        self.import_value(element)
+    }
+
+    fn set_index(
+        &mut self,
+        object: Value,
+        index: Value,
+        value: Value,
+        bracket: &Token,
+    ) -> Result<(), RuntimeError> {
+        self.export(object)
+            .set_index(&self.export(index), self.export(value), bracket)?;
+        self.resize_value(object)
+    }
+
+    // Lists and maps are handed over as they are, so host code shares them
+    // with the heap.
+    fn export(&self, value: Value) -> HostValue {
+        let Some(obj) = value.as_obj() else {
+            if let Some(n) = value.as_number() {
+                return HostValue::Number(n);
+            }
+            return match value.as_boolean() {
+                Some(b) => HostValue::Boolean(b),
+                None => HostValue::Nil,
+            };
+        };
+
+        match self.heap.get(obj) {
+            Obj::String(s) => HostValue::String(Rc::clone(s)),
+            Obj::Native(native) | Obj::Method(_, native) => HostValue::Native(Rc::clone(native)),
+            Obj::Class(class) => HostValue::Class(Rc::clone(class)),
+            Obj::Instance(instance) => HostValue::Instance(Rc::clone(instance)),
+            Obj::BoundMethod(method) => HostValue::BoundMethod(Rc::clone(method)),
+            Obj::List(list) => HostValue::List(Rc::clone(list)),
+            Obj::Map(map) => HostValue::Map(Rc::clone(map)),
+        }
+    }
+
+    // Each host object gets a single heap object, so values that are the same
+    // to the host are equal in scripts too.
+    fn import_value(&mut self, value: HostValue) -> Result<Value, RuntimeError> {
+        match value {
+            HostValue::Nil => Ok(Value::nil()),
+            HostValue::Boolean(b) => Ok(Value::boolean(b)),
+            HostValue::Number(n) => Ok(Value::number(n)),
+            HostValue::String(s) => self.allocate_string(s),
+            HostValue::Native(native) => self.allocate_host(Obj::Native(native)),
+            HostValue::Class(class) => self.allocate_host(Obj::Class(class)),
+            HostValue::Instance(instance) => self.allocate_host(Obj::Instance(instance)),
+            HostValue::BoundMethod(method) => self.allocate_host(Obj::BoundMethod(method)),
+            HostValue::List(list) => self.allocate_host(Obj::List(list)),
+            HostValue::Map(map) => self.allocate_host(Obj::Map(map)),
+        }
+    }
+
+    fn allocate_host(&mut self, obj: Obj) -> Result<Value, RuntimeError> {
+        match self.heap.find_host(&obj) {
+            Some(existing) => Ok(Value::obj(existing)),
+            None => self.allocate_value(obj),
+        }
+    }
+
+    fn as_string(&self, value: Value) -> Option<&str> {
+        value.as_obj().and_then(|obj| self.heap.as_str(obj))
+    }
+
+    fn is_container(&self, value: Value) -> bool {
+        matches!(
+            value.as_obj().map(|obj| self.heap.get(obj)),
+            Some(Obj::List(_) | Obj::Map(_))
+        )
+    }
+
+    fn allocate_string(
+        &mut self,
+        string: impl Into<Rc<str>> + AsRef<str>,
+    ) -> Result<Value, RuntimeError> {
+        match self.heap.find_string(string.as_ref()) {
+            Some(obj) => Ok(Value::obj(obj)),
+            None => self.allocate_value(Obj::String(string.into())),
+        }
+    }
+
+    fn allocate_value(&mut self, obj: Obj) -> Result<Value, RuntimeError> {
+        self.allocate(obj).map(Value::obj)
+    }
+
+    fn allocate(&mut self, obj: Obj) -> Result<ObjRef, RuntimeError> {
+        self.make_room(obj.size())?;
+        Ok(self.heap.alloc(obj))
+    }
+
+    // Accounts for a list or map that was mutated, which may take the heap
+    // past its limit just like an allocation.
+    fn resize(&mut self, obj: ObjRef) -> Result<(), RuntimeError> {
+        self.heap.resize(obj);
+        self.make_room(0)
+    }
+
+    fn resize_value(&mut self, value: Value) -> Result<(), RuntimeError> {
+        match value.as_obj() {
+            Some(obj) => self.resize(obj),
+            None => Ok(()),
+        }
+    }
+
+    // Collections only happen during a run, when the constants of the running
+    // chunk are known. Globals the host defines outside of a run are allocated
+    // directly and don't count against the heap limit.
+    fn make_room(&mut self, size: usize) -> Result<(), RuntimeError> {
+        let Some(constants) = &self.constants else {
+            return Ok(());
+        };
+
+        if self.heap.should_collect(size) {
+            let globals = self.globals.iter().flat_map(|(k, v)| [Value::obj(*k), *v]);
+            let roots = self.stack.iter().chain(constants);
+            self.heap.collect(roots.copied().chain(globals));
+        }
+
+        match self.heap.max_heap() {
+            Some(max_heap) if self.heap.exceeds_limit(size) => Err(OutOfMemory(max_heap)),
+            _ => Ok(()),
+        }
+    }
+
+    fn property_name(&self, chunk: &Chunk, offset: usize, index: u16) -> Token {
+        let name = self.as_string(*chunk.constant(index)).unwrap_or_default();
+        Token::new(
+            TokenType::Identifier(Symbol::intern(name)),
+            Span::new(chunk.line(offset)),
+        )
+    }
+
+    fn peek(&self, distance: usize) -> Value {
+        self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("Error: VM stack underflow")
+    }
+
+    fn pop_pair(&mut self) -> (Value, Value) {
+        let right = self.pop();
+        let left = self.pop();
+        (left, right)
+    }
+
+    fn number_op(
+        &mut self,
+        operator: &str,
+        func: fn(f64, f64) -> Value,
+    ) -> Result<(), RuntimeError> {
+        let (left, right) = self.pop_pair();
+        if let (Some(n1), Some(n2)) = (left.as_number(), right.as_number()) {
+            self.push(func(n1, n2));
+            Ok(())
+        } else {
+            Err(ExpectedNumberLiterals(operator.to_string()))
+        }
+    }
+}
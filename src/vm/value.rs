@@ -0,0 +1,223 @@
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::value::Value as HostValue;
+use crate::vm::gc::{Heap, Obj};
+
+#[cfg(not(feature = "nan-boxing"))]
+pub use self::tagged::Value;
+
+#[cfg(feature = "nan-boxing")]
+pub use self::boxed::Value;
+
+#[cfg(not(feature = "nan-boxing"))]
+mod tagged {
+    use crate::vm::gc::ObjRef;
+
+    #[derive(Clone, Copy, Debug)]
+    pub enum Value {
+        Nil,
+        Boolean(bool),
+        Number(f64),
+        Obj(ObjRef),
+    }
+
+    impl Value {
+        pub fn nil() -> Value {
+            Value::Nil
+        }
+
+        pub fn boolean(b: bool) -> Value {
+            Value::Boolean(b)
+        }
+
+        pub fn number(n: f64) -> Value {
+            Value::Number(n)
+        }
+
+        pub fn obj(obj: ObjRef) -> Value {
+            Value::Obj(obj)
+        }
+
+        pub fn is_nil(&self) -> bool {
+            matches!(self, Value::Nil)
+        }
+
+        pub fn as_boolean(&self) -> Option<bool> {
+            match self {
+                Value::Boolean(b) => Some(*b),
+                _ => None,
+            }
+        }
+
+        pub fn as_number(&self) -> Option<f64> {
+            match self {
+                Value::Number(n) => Some(*n),
+                _ => None,
+            }
+        }
+
+        pub fn as_obj(&self) -> Option<ObjRef> {
+            match self {
+                Value::Obj(obj) => Some(*obj),
+                _ => None,
+            }
+        }
+    }
+}
+
+// Numbers are stored as their own bits. Every other value lives inside the
+// quiet NaN space: nil and booleans as small tags, objects as the sign bit
+// plus their heap index. Computed NaNs are canonicalized so they can never be
+// mistaken for a tagged value.
+#[cfg(feature = "nan-boxing")]
+mod boxed {
+    use crate::vm::gc::ObjRef;
+
+    const SIGN_BIT: u64 = 0x8000_0000_0000_0000;
+    const QNAN: u64 = 0x7ffc_0000_0000_0000;
+
+    const TAG_NIL: u64 = 1;
+    const TAG_FALSE: u64 = 2;
+    const TAG_TRUE: u64 = 3;
+
+    const NIL: u64 = QNAN | TAG_NIL;
+    const FALSE: u64 = QNAN | TAG_FALSE;
+    const TRUE: u64 = QNAN | TAG_TRUE;
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Value(u64);
+
+    impl Value {
+        pub fn nil() -> Value {
+            Value(NIL)
+        }
+
+        pub fn boolean(b: bool) -> Value {
+            if b {
+                Value(TRUE)
+            } else {
+                Value(FALSE)
+            }
+        }
+
+        pub fn number(n: f64) -> Value {
+            if n.is_nan() {
+                Value(f64::NAN.to_bits())
+            } else {
+                Value(n.to_bits())
+            }
+        }
+
+        pub fn obj(obj: ObjRef) -> Value {
+            Value(SIGN_BIT | QNAN | obj.index() as u64)
+        }
+
+        pub fn is_nil(&self) -> bool {
+            self.0 == NIL
+        }
+
+        pub fn as_boolean(&self) -> Option<bool> {
+            match self.0 {
+                TRUE => Some(true),
+                FALSE => Some(false),
+                _ => None,
+            }
+        }
+
+        pub fn as_number(&self) -> Option<f64> {
+            if self.0 & QNAN != QNAN {
+                Some(f64::from_bits(self.0))
+            } else {
+                None
+            }
+        }
+
+        pub fn as_obj(&self) -> Option<ObjRef> {
+            if self.0 & (SIGN_BIT | QNAN) == SIGN_BIT | QNAN {
+                Some(ObjRef::from_index(self.0 as u32))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        if self.is_nil() {
+            false
+        } else {
+            self.as_boolean().unwrap_or(true)
+        }
+    }
+
+    pub fn is_equal(&self, other: &Value) -> bool {
+        if let (Some(n1), Some(n2)) = (self.as_number(), other.as_number()) {
+            return (n1 - n2).abs() < 0.01;
+        }
+
+        match (self.as_boolean(), other.as_boolean()) {
+            (Some(b1), Some(b2)) => return b1 == b2,
+            (Some(_), None) | (None, Some(_)) => return false,
+            (None, None) => {}
+        }
+
+        match (self.as_obj(), other.as_obj()) {
+            (Some(o1), Some(o2)) => o1 == o2,
+            (None, None) => self.is_nil() && other.is_nil(),
+            _ => false,
+        }
+    }
+
+    pub fn type_name(&self, heap: &Heap) -> &'static str {
+        if self.is_nil() {
+            return "nil";
+        } else if self.as_boolean().is_some() {
+            return "boolean";
+        }
+
+        match self.as_obj().map(|obj| heap.get(obj)) {
+            None => "number",
+            Some(Obj::String(_)) => "string",
+            Some(Obj::Native(_) | Obj::BoundMethod(_) | Obj::Method(..)) => "function",
+            Some(Obj::Class(_)) => "class",
+            Some(Obj::Instance(_)) => "instance",
+            Some(Obj::List(_)) => "list",
+            Some(Obj::Map(_)) => "map",
+        }
+    }
+
+    pub fn display<'a>(&'a self, heap: &'a Heap) -> ValueDisplay<'a> {
+        ValueDisplay { value: self, heap }
+    }
+}
+
+pub struct ValueDisplay<'a> {
+    value: &'a Value,
+    heap: &'a Heap,
+}
+
+impl Display for ValueDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(n) = self.value.as_number() {
+            n.fmt(f)
+        } else if let Some(b) = self.value.as_boolean() {
+            b.fmt(f)
+        } else if let Some(obj) = self.value.as_obj() {
+            match self.heap.get(obj) {
+                Obj::String(s) => s.fmt(f),
+                Obj::Native(native) | Obj::Method(_, native) => {
+                    write!(f, "<native fn {}>", native.name())
+                }
+                Obj::Class(class) => class.name().fmt(f),
+                Obj::Instance(instance) => write!(f, "{} instance", instance.class().name()),
+                Obj::BoundMethod(method) => write!(f, "<native fn {}>", method.name()),
+                Obj::List(list) => HostValue::List(Rc::clone(list)).fmt(f),
+                Obj::Map(map) => HostValue::Map(Rc::clone(map)).fmt(f),
+            }
+        } else {
+            write!(f, "nil")
+        }
+    }
+}
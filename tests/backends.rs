@@ -0,0 +1,113 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::{err, eval, eval_session, lox, ok};
+use rust_lox::{Backend, Value};
+
+// Each snippet only has to behave the same on both backends, errors included.
+// The VM represents values differently with `--features nan-boxing`, so run
+// these both with and without it.
+const SNIPPETS: [&str; 40] = [
+    "1 + 2 * 3",
+    "(1 + 2) * 3 / 4 - -1",
+    "!nil == !false",
+    "\"ab\" == \"a\" + \"b\"",
+    "1 < 2 == 2 <= 2",
+    "\"a\" + 1",
+    "-\"a\"",
+    "nope",
+    "1()",
+    "len()",
+    "len(1, 2)",
+    "str(1.5) + str(nil) + str(true)",
+    "type(1) + type(\"\") + type(nil) + type(len)",
+    "num(\"12.5\") + 1",
+    "num(\"x\")",
+    "[1, [2, \"x\"], {\"a\": 1}]",
+    "[3, 1, 2].sort",
+    "[[3, 1, 2]].map(len)",
+    "[1, 2, 3][-1]",
+    "[1, 2][0.5]",
+    "[\"a\", 1].sort()",
+    "[1, 2].foo",
+    "1.foo",
+    "[1, 2, 3].reduce(num, 0)",
+    "[1, 2, 3].reduce(assert, 1)",
+    "[\"1\", \"2\"].map(num)",
+    "[1].insert(1, 5)",
+    "[1, 2][0] = 9",
+    "{\"a\": 1}[\"b\"] = 2",
+    "{nil: 1, true: 2, 3: 4}",
+    "{[1]: 2}",
+    "{\"k\": \"v\"}.values().join(\"\")",
+    "str([1, [2, {\"k\": [3]}]])",
+    "len([1, 2]) + len({\"a\": 1}) + len(\"abc\")",
+    "type([]) + type({}) + type([].push)",
+    "\"a,b,c\".split(\",\").map(len)",
+    "\"x${[1, 2]}y${{\"a\": nil}}\"",
+    "\"abc\".upper",
+    "assert(1 == 2, \"boom\")",
+    "str(\n  [1,\n   2].map(\n    len))",
+];
+
+#[test]
+fn backends_agree() {
+    eval_session(&[], &SNIPPETS);
+}
+
+#[test]
+fn errors_carry_a_call_trace() {
+    assert_eq!(
+        eval("str(\n  [1,\n   2].map(\n    len))"),
+        err("Expected string, list or map but got number\n[line 4] in map()")
+    );
+    assert_eq!(
+        eval("[1, 2].reduce(assert, 1)"),
+        err("Assertion failed: 2\n[line 1] in reduce()")
+    );
+}
+
+#[test]
+fn host_values_keep_their_identity() {
+    let results = eval_session(
+        &[("shared", "[1]")],
+        &["[shared, shared].map(len)", "shared.push(2)", "shared"],
+    );
+    assert_eq!(results, [ok("[1, 1]"), ok("nil"), ok("[1, 2]")]);
+}
+
+// Host code is handed the same lists and maps that scripts see, so changes made
+// on either side show up on the other.
+#[test]
+fn host_shares_lists_and_maps() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut lox = lox(backend);
+        let kept = Rc::new(RefCell::new(Vec::new()));
+        let keeper = Rc::clone(&kept);
+        lox.define_native("keep", 1, move |_, arguments| {
+            keeper.borrow_mut().push(arguments[0].clone());
+            Ok(Value::Nil)
+        });
+        let list = lox.eval("[1]").unwrap();
+        lox.set_global("list", list);
+        let map = lox.eval("{}").unwrap();
+        lox.set_global("map", map);
+
+        lox.eval("keep(list) == keep(map)").unwrap();
+        let list = lox.get_global("list").unwrap();
+        lox.call_method(&list, "push", &[Value::Number(2.0)])
+            .unwrap();
+        lox.eval("list.push(3)").unwrap();
+        lox.eval("map[\"a\"] = list").unwrap();
+
+        assert_eq!(lox.eval("list").unwrap().to_string(), "[1, 2, 3]");
+        assert_eq!(list.to_string(), "[1, 2, 3]");
+        let kept: Vec<String> = kept.borrow().iter().map(Value::to_string).collect();
+        assert_eq!(kept, ["[1, 2, 3]", "{a: [1, 2, 3]}"], "{:?}", backend);
+
+        lox.set_global("again", list);
+        assert_eq!(lox.eval("again == list").unwrap().to_string(), "true");
+    }
+}
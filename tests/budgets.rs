@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use rust_lox::{Backend, Lox, LoxBuilder, SharedBuffer};
+
+fn eval(builder: LoxBuilder, sources: &[&str]) -> Vec<Result<String, String>> {
+    let mut lox = builder.stdout(SharedBuffer::new()).build();
+    sources
+        .iter()
+        .map(|source| {
+            lox.eval(source)
+                .map(|value| value.to_string())
+                .map_err(|error| error.to_string())
+        })
+        .collect()
+}
+
+fn long_list() -> String {
+    format!("[{}].len()", vec!["1"; 1000].join(", "))
+}
+
+#[test]
+fn steps_are_limited() {
+    // The tree-walker counts expressions and the VM counts instructions, so
+    // `1 + 2` takes three steps on one and four on the other.
+    for (backend, steps) in [(Backend::TreeWalker, 3), (Backend::Vm, 4)] {
+        let builder = || Lox::builder().backend(backend);
+        assert_eq!(
+            eval(builder().max_steps(steps), &["1 + 2"]),
+            [Ok("3".to_string())]
+        );
+        assert_eq!(
+            eval(builder().max_steps(steps - 1), &["1 + 2"]),
+            [Err(format!(
+                "Execution budget of {} steps exceeded",
+                steps - 1
+            ))]
+        );
+
+        // Every script gets the whole budget again.
+        let results = eval(builder().max_steps(steps), &["1 + 2", "1 + 2", "1 + 2 + 3"]);
+        assert_eq!(
+            results,
+            [
+                Ok("3".to_string()),
+                Ok("3".to_string()),
+                Err(format!("Execution budget of {} steps exceeded", steps))
+            ]
+        );
+    }
+}
+
+#[test]
+fn time_is_limited() {
+    let source = long_list();
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let builder = || Lox::builder().backend(backend);
+        assert_eq!(
+            eval(builder().timeout(Duration::ZERO), &[&source]),
+            [Err("Execution timed out after 0ns".to_string())]
+        );
+        assert_eq!(
+            eval(builder().timeout(Duration::from_secs(60)), &[&source]),
+            [Ok("1000".to_string())]
+        );
+        assert_eq!(eval(builder(), &[&source]), [Ok("1000".to_string())]);
+    }
+}
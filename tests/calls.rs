@@ -0,0 +1,126 @@
+mod common;
+
+use common::{err, lox, ok};
+use rust_lox::{Backend, ClassBuilder, FromLox, RuntimeError, Symbol, Value};
+
+const BACKENDS: [Backend; 2] = [Backend::TreeWalker, Backend::Vm];
+
+fn show(result: Result<Value, RuntimeError>) -> Result<String, String> {
+    result
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.into())
+}
+
+#[test]
+fn calling_globals() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        lox.define_native("add", 2, |_, arguments| {
+            let (a, b) = (f64::from_lox(&arguments[0])?, f64::from_lox(&arguments[1])?);
+            Ok(Value::Number(a + b))
+        });
+
+        let two = [Value::Number(1.0), Value::Number(1.0)];
+        assert_eq!(show(lox.call("add", &two)), ok("2"));
+        assert_eq!(show(lox.call("str", &[Value::Boolean(true)])), ok("true"));
+        assert_eq!(show(lox.call("nope", &[])), err("Undefined variable: nope"));
+        assert_eq!(
+            show(lox.call("add", &two[..1])),
+            err("Expected 2 arguments but got 1: add\nin add()")
+        );
+    }
+}
+
+#[test]
+fn calling_methods() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        let counter = ClassBuilder::new("Counter", 0, |_| Ok(0.0))
+            .method("add", 1, |count, arguments| {
+                *count += f64::from_lox(&arguments[0])?;
+                Ok(Value::Nil)
+            })
+            .getter("count", |count| Ok(Value::Number(*count)));
+        lox.define_class(counter);
+        for (name, source) in [
+            ("list", "[1]"),
+            ("map", "{\"a\": 1}"),
+            ("counter", "Counter()"),
+        ] {
+            let value = lox.eval(source).unwrap();
+            lox.set_global(name, value);
+        }
+
+        // The mutations land in the values the script sees.
+        let list = lox.get_global("list").unwrap();
+        lox.call_method(&list, "push", &[Value::Number(2.0)])
+            .unwrap();
+        assert_eq!(lox.eval("list").unwrap().to_string(), "[1, 2]");
+
+        let map = lox.get_global("map").unwrap();
+        let removed = lox.call_method(&map, "remove", &[string("a")]);
+        assert_eq!(show(removed), ok("1"));
+        assert_eq!(lox.eval("map").unwrap().to_string(), "{}");
+
+        let counter = lox.get_global("counter").unwrap();
+        lox.call_method(&counter, "add", &[Value::Number(5.0)])
+            .unwrap();
+        assert_eq!(lox.eval("counter.count").unwrap().to_string(), "5");
+
+        let upper = lox.call_method(&string("abc"), "upper", &[]);
+        assert_eq!(show(upper), ok("ABC"));
+        assert_eq!(
+            show(lox.call_method(&list, "nope", &[])),
+            err("Undefined method 'nope' on list")
+        );
+        assert_eq!(
+            show(lox.call_method(&Value::Nil, "len", &[])),
+            err("Undefined method 'len' on nil")
+        );
+    }
+}
+
+#[test]
+fn errors_carry_a_trace() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        let list = lox.eval("[\"boom\"]").unwrap();
+        let panic = lox.get_global("panic").unwrap();
+
+        let Err(error) = lox.call_method(&list, "map", &[panic]) else {
+            panic!("Expected the callback to fail");
+        };
+        assert!(matches!(error.cause(), RuntimeError::Panic(_)));
+        let frames: Vec<String> = error.trace().iter().map(ToString::to_string).collect();
+        assert_eq!(frames, ["in map()"]);
+
+        assert_eq!(
+            show(lox.call("num", &[string("x")])),
+            err("Could not parse 'x' as a number\nin num()")
+        );
+    }
+}
+
+#[test]
+fn looking_up_names_does_not_intern_them() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        let list = lox.eval("[]").unwrap();
+        assert!(lox.get_global("unknownGlobal").is_none());
+        assert_eq!(
+            show(lox.call("unknownFunction", &[])),
+            err("Undefined variable: unknownFunction")
+        );
+        assert_eq!(
+            show(lox.call_method(&list, "unknownMethod", &[])),
+            err("Undefined method 'unknownMethod' on list")
+        );
+        for name in ["unknownGlobal", "unknownFunction", "unknownMethod"] {
+            assert!(Symbol::lookup(name).is_none(), "{}", name);
+        }
+    }
+}
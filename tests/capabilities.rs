@@ -0,0 +1,99 @@
+use rust_lox::{Backend, Capabilities, Capability, Error, Lox, SharedBuffer, Value};
+
+const BACKENDS: [Backend; 2] = [Backend::TreeWalker, Backend::Vm];
+
+fn build(backend: Backend, capabilities: Capabilities) -> Lox {
+    Lox::builder()
+        .backend(backend)
+        .capabilities(capabilities)
+        .stdout(SharedBuffer::new())
+        .build()
+}
+
+fn eval(lox: &mut Lox, source: &str) -> Result<String, String> {
+    lox.eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+fn denied(capability: &str, function: &str) -> Result<String, String> {
+    Err(format!(
+        "Permission denied: missing '{}' capability\n[line 1] in {}()",
+        capability, function
+    ))
+}
+
+#[test]
+fn only_stdout_is_granted_by_default() {
+    let capabilities = Capabilities::default();
+    assert!(capabilities.has(Capability::Stdout));
+    for capability in Capability::ALL {
+        if capability != Capability::Stdout {
+            assert!(!capabilities.has(capability), "{}", capability);
+        }
+    }
+
+    for backend in BACKENDS {
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .stdout(SharedBuffer::new())
+            .build();
+        assert_eq!(eval(&mut lox, "clock()"), denied("clock", "clock"));
+        assert_eq!(eval(&mut lox, "input()"), denied("stdin", "input"));
+
+        let mut lox = build(backend, Capabilities::all());
+        assert_eq!(eval(&mut lox, "type(clock())"), Ok("number".to_string()));
+    }
+}
+
+#[test]
+fn denying_stdout_stops_all_output() {
+    let mut capabilities = Capabilities::all();
+    capabilities.revoke(Capability::Stdout);
+
+    for backend in BACKENDS {
+        let stdout = SharedBuffer::new();
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .capabilities(capabilities)
+            .dump_ast(true)
+            .trace(true)
+            .stdout(stdout.clone())
+            .build();
+        assert!(matches!(lox.eval("1 + 2"), Err(Error::Runtime(_))));
+        lox.run("1 + 2");
+        assert_eq!(stdout.contents(), "");
+    }
+}
+
+#[test]
+fn natives_can_check_capabilities() {
+    for backend in BACKENDS {
+        for capabilities in [Capabilities::default(), Capabilities::all()] {
+            let mut lox = build(backend, capabilities);
+            lox.define_native("home", 0, |context, _| {
+                context.check(Capability::Env)?;
+                Ok(Value::String("/home".into()))
+            });
+
+            let expected = if capabilities.has(Capability::Env) {
+                Ok("/home".to_string())
+            } else {
+                denied("env", "home")
+            };
+            assert_eq!(eval(&mut lox, "home()"), expected);
+        }
+
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .native("canRead", 0, |context, _| {
+                Ok(Value::Boolean(
+                    context.capabilities().has(Capability::FsRead),
+                ))
+            })
+            .capabilities(Capabilities::none())
+            .stdout(SharedBuffer::new())
+            .build();
+        assert_eq!(eval(&mut lox, "canRead()"), Ok("false".to_string()));
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use common::{err, lox, ok};
+use rust_lox::{Backend, ClassBuilder, FromLox, Lox, Value};
+
+const BACKENDS: [Backend; 2] = [Backend::TreeWalker, Backend::Vm];
+
+fn eval(lox: &mut Lox, source: &str) -> Result<String, String> {
+    lox.eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+struct Counter {
+    count: f64,
+}
+
+fn counter() -> ClassBuilder<Counter> {
+    ClassBuilder::new("Counter", 1, |arguments| {
+        Ok(Counter {
+            count: f64::from_lox(&arguments[0])?,
+        })
+    })
+    .method("add", 1, |counter, arguments| {
+        counter.count += f64::from_lox(&arguments[0])?;
+        Ok(Value::Number(counter.count))
+    })
+    .method("apply", 1, |_, arguments| {
+        arguments[0].call(&[Value::Number(1.0)])
+    })
+    .getter("count", |counter| Ok(Value::Number(counter.count)))
+    .setter("count", |counter, value| {
+        counter.count = f64::from_lox(&value)?;
+        Ok(())
+    })
+    .getter("double", |counter| Ok(Value::Number(counter.count * 2.0)))
+}
+
+#[test]
+fn scripts_use_host_classes() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        lox.define_class(counter());
+        let instance = lox.eval("Counter(1)").unwrap();
+        lox.set_global("c", instance);
+
+        assert_eq!(eval(&mut lox, "c.add(2) + c.count"), ok("6"));
+        assert_eq!(eval(&mut lox, "c.count = 10"), ok("10"));
+        assert_eq!(eval(&mut lox, "c.double"), ok("20"));
+        assert_eq!(eval(&mut lox, "type(c)"), ok("instance"));
+        assert_eq!(
+            eval(&mut lox, "Counter(\"x\")"),
+            err("Expected number but got string\n[line 1] in Counter()")
+        );
+        assert_eq!(
+            eval(&mut lox, "c.double = 1"),
+            err("Undefined property: Identifier(\"double\") on line 1")
+        );
+        assert_eq!(
+            eval(&mut lox, "c.count = \"x\""),
+            err("Expected number but got string")
+        );
+        assert_eq!(
+            eval(&mut lox, "c.nope"),
+            err("Undefined property: Identifier(\"nope\") on line 1")
+        );
+    }
+}
+
+// A method can't reach its own instance again while it holds the data.
+#[test]
+fn instances_in_use_are_rejected() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        lox.define_class(counter());
+        let instance = lox.eval("Counter(1)").unwrap();
+        lox.set_global("c", instance);
+
+        assert_eq!(
+            eval(&mut lox, "c.apply(c.add)"),
+            err(concat!(
+                "Can't use a Counter instance while one of its methods is running\n",
+                "[line 1] in apply()"
+            ))
+        );
+        // Other instances of the class are free to use.
+        assert_eq!(eval(&mut lox, "Counter(5).apply(c.add)"), ok("2"));
+        assert_eq!(eval(&mut lox, "c.count"), ok("2"));
+    }
+}
+
+struct Tracked(Rc<Cell<usize>>);
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn host_data_is_dropped_with_its_instance() {
+    for backend in BACKENDS {
+        let drops = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&drops);
+        let mut lox = lox(backend);
+        lox.define_class(ClassBuilder::new("Tracked", 0, move |_| {
+            Ok(Tracked(Rc::clone(&counter)))
+        }));
+
+        let kept = lox.eval("Tracked()").unwrap();
+        lox.set_global("kept", kept);
+        eval(&mut lox, "[Tracked(), Tracked()].len()").unwrap();
+        // The VM frees unreachable instances on its next collection, which
+        // stress mode runs on every allocation.
+        eval(&mut lox, "[1]").unwrap();
+        assert_eq!(drops.get(), 2, "{:?}", backend);
+
+        lox.set_global("kept", Value::Nil);
+        eval(&mut lox, "[1]").unwrap();
+        assert_eq!(drops.get(), 3, "{:?}", backend);
+    }
+}
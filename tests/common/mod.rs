@@ -0,0 +1,67 @@
+// Each test binary only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use rust_lox::{Backend, Lox, SharedBuffer};
+
+// Collects on every allocation, so that anything the VM forgets to root is
+// freed while it's still in use.
+pub fn lox(backend: Backend) -> Lox {
+    Lox::builder()
+        .backend(backend)
+        .gc_stress(true)
+        .stdout(SharedBuffer::new())
+        .stderr(SharedBuffer::new())
+        .build()
+}
+
+fn run(
+    backend: Backend,
+    globals: &[(&str, &str)],
+    sources: &[&str],
+) -> Vec<Result<String, String>> {
+    let mut lox = lox(backend);
+    for (name, source) in globals {
+        let value = lox.eval(source).expect("Error evaluating global");
+        lox.set_global(name, value);
+    }
+
+    sources
+        .iter()
+        .map(|source| {
+            lox.eval(source)
+                .map(|value| value.to_string())
+                .map_err(|error| error.to_string())
+        })
+        .collect()
+}
+
+// Binds each global to the value of its source, then evaluates `sources` in
+// order on both backends, which must agree on every result.
+pub fn eval_session(globals: &[(&str, &str)], sources: &[&str]) -> Vec<Result<String, String>> {
+    let tree = run(Backend::TreeWalker, globals, sources);
+    let vm = run(Backend::Vm, globals, sources);
+    assert_eq!(tree, vm, "Backends disagree on {:?}", sources);
+    tree
+}
+
+pub fn eval(source: &str) -> Result<String, String> {
+    eval_session(&[], &[source]).remove(0)
+}
+
+pub fn ok(value: &str) -> Result<String, String> {
+    Ok(value.to_string())
+}
+
+pub fn err(message: &str) -> Result<String, String> {
+    Err(message.to_string())
+}
+
+// Writes `source` to a file of its own for the tests that run scripts.
+pub fn script(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("lox-{}-{}.lox", std::process::id(), name));
+    fs::write(&path, source).unwrap();
+    path
+}
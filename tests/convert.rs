@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use rust_lox::{FromLox, IntoLox, Value};
+
+fn number(n: f64) -> Value {
+    Value::Number(n)
+}
+
+#[test]
+fn integer_bounds() {
+    let two_63 = 2f64.powi(63);
+    let two_64 = 2f64.powi(64);
+
+    assert_eq!(i64::from_lox(&number(-two_63)).ok(), Some(i64::MIN));
+    assert!(i64::from_lox(&number(two_63)).is_err());
+    assert_eq!(
+        i64::from_lox(&number(two_63 - 1024.0)).ok(),
+        Some(i64::MAX - 1023)
+    );
+    assert!(u64::from_lox(&number(two_64)).is_err());
+    assert_eq!(
+        u64::from_lox(&number(two_64 - 2048.0)).ok(),
+        Some(u64::MAX - 2047)
+    );
+    assert!(u64::from_lox(&number(-1.0)).is_err());
+    assert_eq!(usize::from_lox(&number(0.0)).ok(), Some(0));
+    assert!(usize::from_lox(&number(-1.0)).is_err());
+    assert!(usize::from_lox(&number(usize::MAX as f64)).is_err());
+
+    assert_eq!(u8::from_lox(&number(255.0)).ok(), Some(255));
+    assert!(u8::from_lox(&number(256.0)).is_err());
+    assert_eq!(i8::from_lox(&number(-128.0)).ok(), Some(-128));
+    assert!(i8::from_lox(&number(128.0)).is_err());
+    assert_eq!(i32::from_lox(&number(i32::MAX as f64)).ok(), Some(i32::MAX));
+    assert!(i32::from_lox(&number(i32::MAX as f64 + 1.0)).is_err());
+}
+
+#[test]
+fn integers_must_be_whole() {
+    assert!(i32::from_lox(&number(0.5)).is_err());
+    assert!(i64::from_lox(&number(f64::NAN)).is_err());
+    assert!(i64::from_lox(&number(f64::INFINITY)).is_err());
+    assert_eq!(
+        i64::from_lox(&number(1.5)).err().map(|e| e.to_string()),
+        Some("Cannot convert 1.5 to i64".to_string())
+    );
+    assert_eq!(
+        i64::from_lox(&Value::Nil).err().map(|e| e.to_string()),
+        Some("Expected number but got nil".to_string())
+    );
+}
+
+#[test]
+fn round_trips() {
+    assert_eq!(
+        String::from_lox(&"abc".into_lox()).ok(),
+        Some("abc".to_string())
+    );
+    assert_eq!(bool::from_lox(&true.into_lox()).ok(), Some(true));
+    assert_eq!(f64::from_lox(&1.5f64.into_lox()).ok(), Some(1.5));
+    assert_eq!(Option::<u8>::from_lox(&Value::Nil).ok(), Some(None));
+    assert_eq!(Option::<u8>::from_lox(&7u8.into_lox()).ok(), Some(Some(7)));
+    assert!(<()>::from_lox(&().into_lox()).is_ok());
+
+    let list = vec![1, 2, 3].into_lox();
+    assert_eq!(list.to_string(), "[1, 2, 3]");
+    assert_eq!(Vec::<i32>::from_lox(&list).ok(), Some(vec![1, 2, 3]));
+    assert!(Vec::<String>::from_lox(&list).is_err());
+
+    let map = HashMap::from([("a".to_string(), 1.0)]).into_lox();
+    assert_eq!(map.to_string(), "{a: 1}");
+    assert_eq!(
+        HashMap::<String, f64>::from_lox(&map).ok(),
+        Some(HashMap::from([("a".to_string(), 1.0)]))
+    );
+    assert!(HashMap::<String, f64>::from_lox(&list).is_err());
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::fs;
+
+use common::script;
+use rust_lox::{Backend, Lox, SharedBuffer};
+
+const SOURCE: &str = "[1, 2][0] + 3";
+
+#[test]
+fn chunks_can_be_disassembled() {
+    let path = script("disasm", SOURCE);
+    let stdout = SharedBuffer::new();
+    let mut lox = Lox::builder().stdout(stdout.clone()).build();
+
+    assert!(lox.disassemble_file(path.to_str().unwrap()).is_ok());
+    assert_eq!(
+        stdout.take(),
+        "\
+== <script> ==
+0000    1 OP_CONSTANT         0 '1'
+0003    | OP_CONSTANT         1 '2'
+0006    | OP_BUILD_LIST       2
+0009    | OP_CONSTANT         2 '0'
+0012    | OP_INDEX
+0013    | OP_CONSTANT         3 '3'
+0016    | OP_ADD
+0017    | OP_RETURN
+"
+    );
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn the_vm_traces_every_instruction() {
+    let stdout = SharedBuffer::new();
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .trace(true)
+        .stdout(stdout.clone())
+        .build();
+
+    assert_eq!(lox.eval(SOURCE).unwrap().to_string(), "4");
+    // Each instruction follows the stack it starts with, which is empty at
+    // first.
+    let empty_stack = " ".repeat(10);
+    assert_eq!(
+        stdout.take(),
+        empty_stack
+            + "
+0000    1 OP_CONSTANT         0 '1'
+          [ 1 ]
+0003    | OP_CONSTANT         1 '2'
+          [ 1 ][ 2 ]
+0006    | OP_BUILD_LIST       2
+          [ [1, 2] ]
+0009    | OP_CONSTANT         2 '0'
+          [ [1, 2] ][ 0 ]
+0012    | OP_INDEX
+          [ 1 ]
+0013    | OP_CONSTANT         3 '3'
+          [ 1 ][ 3 ]
+0016    | OP_ADD
+          [ 4 ]
+0017    | OP_RETURN
+"
+    );
+
+    // Without the option the VM runs silently.
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .stdout(stdout.clone())
+        .build();
+    assert_eq!(lox.eval(SOURCE).unwrap().to_string(), "4");
+    assert_eq!(stdout.contents(), "");
+}
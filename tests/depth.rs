@@ -0,0 +1,99 @@
+mod common;
+
+use std::thread;
+
+use common::{err, eval_session, lox, ok};
+use rust_lox::{Backend, Lox, SharedBuffer};
+
+// The default limits must keep even a debug build within the 2 MB stack that
+// threads get by default.
+const STACK_SIZE: usize = 2 * 1024 * 1024;
+const MAX_DEPTH: usize = 128;
+
+fn nested(depth: usize) -> [String; 6] {
+    [
+        format!("{}1{}", "(".repeat(depth), ")".repeat(depth)),
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth)),
+        format!("{}1{}", "{1: ".repeat(depth), "}".repeat(depth)),
+        format!("{}1", "-".repeat(depth)),
+        format!("1{}", " + 1".repeat(depth)),
+        format!("{}1{}", "\"${".repeat(depth), "}\"".repeat(depth)),
+    ]
+}
+
+fn eval(lox: &mut Lox, source: &str) -> Result<String, String> {
+    lox.eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn deep_nesting_fails_cleanly() {
+    let test = || {
+        for backend in [Backend::TreeWalker, Backend::Vm] {
+            for optimize in [false, true] {
+                let mut lox = Lox::builder()
+                    .backend(backend)
+                    .optimize(optimize)
+                    .stdout(SharedBuffer::new())
+                    .build();
+                for source in nested(MAX_DEPTH - 1) {
+                    assert!(eval(&mut lox, &source).is_ok(), "{}", source);
+                }
+                for source in nested(MAX_DEPTH * 4) {
+                    assert_eq!(eval(&mut lox, &source), err("Stack overflow"));
+                }
+            }
+        }
+    };
+
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn limits_are_configurable() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .max_parse_depth(4)
+            .stdout(SharedBuffer::new())
+            .build();
+        assert_eq!(eval(&mut lox, "[[[1]]]"), ok("[[[1]]]"));
+        assert_eq!(eval(&mut lox, "[[[[[1]]]]]"), err("Stack overflow"));
+    }
+
+    let mut lox = Lox::builder()
+        .max_call_depth(4)
+        .stdout(SharedBuffer::new())
+        .build();
+    assert_eq!(eval(&mut lox, "-(-1)"), ok("1"));
+    assert_eq!(eval(&mut lox, "-(-(-(-1)))"), err("Stack overflow"));
+}
+
+// Builtins call back into values without going through either backend, so
+// these recurse in Rust alone.
+#[test]
+fn recursion_through_builtins_fails_cleanly() {
+    let results = eval_session(&[("xs", "[]")], &["xs.push(xs.map)", "xs.map(xs.map)"]);
+    assert_eq!(
+        results,
+        [ok("nil"), err("Stack overflow\n[line 1] in map()")]
+    );
+
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut lox = lox(backend);
+        let xs = lox.eval("[]").unwrap();
+        lox.set_global("xs", xs.clone());
+        let map = [lox.eval("xs.map").unwrap()];
+        lox.call_method(&xs, "push", &map).unwrap();
+
+        let result = lox.call_method(&xs, "map", &map);
+        let error = result.map(|value| value.to_string()).unwrap_err();
+        assert_eq!(error.to_string(), "Stack overflow\nin map()");
+    }
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::script;
+use rust_lox::{Backend, Error, Lox, SharedBuffer, Value};
+
+#[test]
+fn the_builder_configures_an_interpreter() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let stdout = SharedBuffer::new();
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .global("name", Value::String("lox".into()))
+            .global("answer", Value::Number(42.0))
+            .stdout(stdout.clone())
+            .build();
+
+        let value = lox.eval("name + str(answer)").unwrap();
+        assert_eq!(value.to_string(), "lox42");
+        assert!(matches!(value, Value::String(_)));
+        assert_eq!(stdout.contents(), "");
+
+        // Globals set from the host persist between scripts.
+        lox.set_global("answer", Value::Number(7.0));
+        assert_eq!(lox.eval("answer").unwrap().to_string(), "7");
+        assert!(lox.get_global("len").is_some());
+
+        let mut bare = Lox::builder()
+            .backend(backend)
+            .prelude(false)
+            .stdout(SharedBuffer::new())
+            .build();
+        assert!(bare.get_global("len").is_none());
+        assert!(matches!(bare.eval("len(\"\")"), Err(Error::Runtime(_))));
+    }
+}
+
+#[test]
+fn errors_say_where_they_came_from() {
+    let mut lox = Lox::builder().stdout(SharedBuffer::new()).build();
+    assert!(matches!(lox.eval("\"abc"), Err(Error::Scan(_))));
+    assert!(matches!(lox.eval("1 +"), Err(Error::Parse(_))));
+    assert!(matches!(lox.eval("-nil"), Err(Error::Runtime(_))));
+    assert!(matches!(
+        lox.run_file("/nonexistent/script.lox"),
+        Err(Error::Read(..))
+    ));
+}
+
+#[test]
+fn the_binary_exits_with_sysexits_codes() {
+    let lox = env!("CARGO_BIN_EXE_rust-lox");
+    let run = |args: &[&str]| {
+        let output = Command::new(lox).args(args).output().unwrap();
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    };
+
+    let ok = script("exit-ok", "1 + 2");
+    let failing = script("exit-failing", "1 +");
+    let (ok, failing) = (ok.to_str().unwrap(), failing.to_str().unwrap());
+
+    assert_eq!(run(&[ok]), (Some(0), "3\n".to_string(), String::new()));
+    assert_eq!(
+        run(&[failing]),
+        (Some(65), String::new(), "Expected expression\n".to_string())
+    );
+    assert_eq!(run(&["/nonexistent/script.lox"]).0, Some(66));
+    assert_eq!(run(&["--bogus"]).0, Some(64));
+    assert_eq!(run(&[ok, ok]).0, Some(64));
+
+    let (code, stdout, stderr) = run(&["--help"]);
+    assert_eq!(code, Some(0));
+    assert!(stdout.starts_with("Usage: rust-lox"));
+    assert_eq!(stderr, "");
+
+    let _ = fs::remove_file(ok);
+    let _ = fs::remove_file(failing);
+}
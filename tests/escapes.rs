@@ -0,0 +1,31 @@
+mod common;
+
+use common::{err, eval, ok};
+
+#[test]
+fn escapes() {
+    assert_eq!(eval(r#""a\nb""#), ok("a\nb"));
+    assert_eq!(eval(r#""\t\r\\\"\$\0""#), ok("\t\r\\\"$\0"));
+    assert_eq!(eval(r#""\u{48}\u{e9}\u{1F600}""#), ok("Hé😀"));
+}
+
+#[test]
+fn invalid_escapes() {
+    assert_eq!(
+        eval(r#""\q""#),
+        err("Invalid escape sequence '\\q' on line 1, column 2")
+    );
+    assert_eq!(
+        eval(r#""ab\u{110000}""#),
+        err("Invalid escape sequence '\\u{110000}' on line 1, column 4")
+    );
+    assert_eq!(
+        eval(r#""\u{}""#),
+        err("Invalid escape sequence '\\u{}' on line 1, column 2")
+    );
+    assert_eq!(
+        eval(r#""\u48""#),
+        err("Invalid escape sequence '\\u' on line 1, column 2")
+    );
+    assert_eq!(eval("\"abc"), err("Unterminated string"));
+}
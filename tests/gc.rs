@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{err, ok, script};
+use rust_lox::{Backend, Lox, SharedBuffer};
+
+fn eval(lox: &mut Lox, source: &str) -> Result<String, String> {
+    lox.eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn stats_are_reported() {
+    let path = script("gc-stats", "[1, 2, 3].map(str)");
+    let (stdout, stderr) = (SharedBuffer::new(), SharedBuffer::new());
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .gc_stress(true)
+        .gc_stats(true)
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .build();
+
+    assert!(lox.run_file(path.to_str().unwrap()).is_ok());
+    assert_eq!(stdout.take(), "[1, 2, 3]\n");
+    let stats = stderr.take();
+    let names: Vec<&str> = stats
+        .lines()
+        .map(|line| line.split(':').next().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "collections",
+            "bytes allocated",
+            "bytes freed",
+            "peak heap",
+            "total pause",
+            "max pause"
+        ]
+    );
+    assert!(!stats.contains("collections:     0\n"), "{}", stats);
+
+    assert_eq!(eval(&mut lox, "gcStats()[\"collections\"] > 0"), ok("true"));
+    assert_eq!(eval(&mut lox, "gcStats().keys().len()"), ok("6"));
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn the_heap_can_be_limited() {
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .max_heap(4096)
+        .stdout(SharedBuffer::new())
+        .build();
+    assert_eq!(eval(&mut lox, "[1, 2, 3].len()"), ok("3"));
+
+    let big = format!("[{}]", vec!["\"abcdefgh\""; 256].join(", "));
+    assert_eq!(
+        eval(&mut lox, &big),
+        err("Out of memory: heap limit of 4096 bytes exceeded")
+    );
+    // What was allocated for the failed script is collected again.
+    assert_eq!(eval(&mut lox, "[1, 2, 3].len()"), ok("3"));
+}
+
+#[test]
+fn gc_options_need_the_vm() {
+    let path = script("gc-options", "1 + 2");
+    let lox = env!("CARGO_BIN_EXE_rust-lox");
+    for option in ["--gc-stress", "--gc-stats", "--max-heap=1M"] {
+        let output = Command::new(lox).arg(option).arg(&path).output().unwrap();
+        assert_eq!(output.status.code(), Some(64), "{}", option);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "--gc-stress, --gc-stats and --max-heap need --backend=vm\n"
+        );
+
+        let output = Command::new(lox)
+            .args(["--backend=vm", option])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0), "{}", option);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+    }
+    let _ = fs::remove_file(path);
+}
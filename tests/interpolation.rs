@@ -0,0 +1,31 @@
+mod common;
+
+use common::{err, eval, ok};
+
+#[test]
+fn interpolation() {
+    assert_eq!(eval(r#""1 + 2 = ${1 + 2}""#), ok("1 + 2 = 3"));
+    assert_eq!(eval(r#""${1}${2}""#), ok("12"));
+    assert_eq!(eval(r#""${nil} ${true} ${1.5}""#), ok("nil true 1.5"));
+    assert_eq!(eval(r#""x${[1, "a"]}y""#), ok("x[1, a]y"));
+}
+
+#[test]
+fn nested_interpolation() {
+    assert_eq!(eval(r#""a${"b${"c"}"}d""#), ok("abcd"));
+    assert_eq!(eval(r#""${{"a": 1}["a"]}""#), ok("1"));
+}
+
+#[test]
+fn escaped_interpolation() {
+    assert_eq!(eval(r#""\${x}""#), ok("${x}"));
+}
+
+#[test]
+fn invalid_interpolation() {
+    assert_eq!(eval(r#""${}""#), err("Expected token of type RightBrace"));
+    assert_eq!(
+        eval(r#""${1 +}""#),
+        err("Expected token of type RightBrace")
+    );
+}
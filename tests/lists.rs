@@ -0,0 +1,135 @@
+mod common;
+
+use common::{err, eval, eval_session, ok};
+
+#[test]
+fn literals() {
+    assert_eq!(eval("[]"), ok("[]"));
+    assert_eq!(eval("[1, [2, [3]]]"), ok("[1, [2, [3]]]"));
+    assert_eq!(eval("[1, \"a\", nil, true]"), ok("[1, a, nil, true]"));
+}
+
+#[test]
+fn indexing() {
+    assert_eq!(eval("[1, 2, 3][1]"), ok("2"));
+    assert_eq!(eval("[1, 2][0] = 9"), ok("9"));
+    assert_eq!(
+        eval("[1, 2, 3][5]"),
+        err("Index 5 is out of range for length 3")
+    );
+    assert_eq!(
+        eval("[1, 2, 3][-1]"),
+        err("Index -1 is out of range for length 3")
+    );
+    assert_eq!(
+        eval("[1, 2][2] = 0"),
+        err("Index 2 is out of range for length 2")
+    );
+    assert_eq!(
+        eval("[1, 2][1.5]"),
+        err("Index must be an integer but got 1.5")
+    );
+    assert_eq!(
+        eval("[1, 2][100000000000000000000]"),
+        err("Index 100000000000000000000 is out of range for length 2")
+    );
+    assert_eq!(eval("[1, 2][\"a\"]"), err("Expected number but got string"));
+}
+
+#[test]
+fn mutation() {
+    let results = eval_session(
+        &[("list", "[1, 2]")],
+        &[
+            "list.push(3)",
+            "list.insert(0, 0)",
+            "list.insert(4, 4)",
+            "list.remove(1)",
+            "list.pop()",
+            "list",
+        ],
+    );
+    assert_eq!(
+        results,
+        [
+            ok("nil"),
+            ok("nil"),
+            ok("nil"),
+            ok("1"),
+            ok("4"),
+            ok("[0, 2, 3]")
+        ]
+    );
+
+    assert_eq!(eval("[].pop()"), ok("nil"));
+    assert_eq!(
+        eval("[1].insert(5, 1)"),
+        err("Index 5 is out of range for length 2\n[line 1] in insert()")
+    );
+    assert_eq!(
+        eval("[1].remove(0.5)"),
+        err("Index must be an integer but got 0.5\n[line 1] in remove()")
+    );
+}
+
+#[test]
+fn sorting() {
+    let results = eval_session(
+        &[
+            ("numbers", "[3, 1, 2]"),
+            ("strings", "[\"b\", \"c\", \"a\"]"),
+        ],
+        &["numbers.sort()", "numbers", "strings.sort()", "strings"],
+    );
+    assert_eq!(
+        results,
+        [ok("nil"), ok("[1, 2, 3]"), ok("nil"), ok("[a, b, c]")]
+    );
+
+    assert!(eval("[\"a\", 1].sort()").is_err());
+}
+
+#[test]
+fn methods() {
+    assert_eq!(eval("[1, 2, 3].len()"), ok("3"));
+    assert_eq!(eval("[1, 2, 3].slice(1, 2)"), ok("[2]"));
+    assert_eq!(eval("[1, 2, 3].slice(2, 1)"), ok("[]"));
+    assert_eq!(eval("[1, 2, 3].slice(1, 9)"), ok("[2, 3]"));
+    assert_eq!(
+        eval("[1, 2, 3].slice(-1, 2)"),
+        err("Index -1 is out of range for length 3\n[line 1] in slice()")
+    );
+    assert_eq!(
+        eval("[1, 2, 3].slice(0, 0.5)"),
+        err("Index must be an integer but got 0.5\n[line 1] in slice()")
+    );
+    assert_eq!(eval("[1, \"a\"].contains(\"a\")"), ok("true"));
+    assert_eq!(eval("[1, 2, 3].map(str).join(\"\")"), ok("123"));
+    assert_eq!(
+        eval("[\"a\", \"b\", \"c\"].filter({\"a\": 1, \"c\": 2}.has)"),
+        ok("[a, c]")
+    );
+    assert_eq!(eval("[\"b\", \"a\", \"c\"].join(\"-\")"), ok("b-a-c"));
+    assert_eq!(
+        eval("[1, 2].foo"),
+        err("Undefined property: Identifier(\"foo\") on line 1")
+    );
+}
+
+#[test]
+fn equality_is_identity() {
+    assert_eq!(eval("[1] == [1]"), ok("false"));
+    assert_eq!(
+        eval_session(&[("list", "[1]")], &["list == list"]),
+        [ok("true")]
+    );
+}
+
+#[test]
+fn cycles() {
+    let results = eval_session(
+        &[("list", "[1]")],
+        &["list.push(list)", "list", "str(list)"],
+    );
+    assert_eq!(results, [ok("nil"), ok("[1, [...]]"), ok("[1, [...]]")]);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use common::{err, eval, eval_session, ok};
+
+#[test]
+fn literals() {
+    assert_eq!(eval("{}"), ok("{}"));
+    assert_eq!(eval("{\"a\": 1, \"b\": 2}"), ok("{a: 1, b: 2}"));
+    assert_eq!(
+        eval("{nil: 1, true: 2, 3: 4}"),
+        ok("{nil: 1, true: 2, 3: 4}")
+    );
+    assert_eq!(eval("{1: \"x\", 1: \"y\"}"), ok("{1: y}"));
+    assert_eq!(eval("{0: \"a\", -0: \"b\"}"), ok("{0: b}"));
+}
+
+#[test]
+fn lookup() {
+    assert_eq!(eval("{\"a\": 1, \"b\": 2}[\"b\"]"), ok("2"));
+    assert_eq!(eval("{\"a\": 1}[\"zz\"]"), ok("nil"));
+    assert_eq!(
+        eval("{\"a\": {\"b\": [1, {\"c\": true}]}}[\"a\"][\"b\"][1][\"c\"]"),
+        ok("true")
+    );
+    assert_eq!(eval("{\"a\": 1}[\"b\"] = 2"), ok("2"));
+}
+
+#[test]
+fn unhashable_keys() {
+    assert_eq!(eval("{[1]: 2}"), err("Can't use list as a map key"));
+    assert_eq!(eval("{\"a\": 1}[{}]"), err("Can't use map as a map key"));
+    assert_eq!(
+        eval("{}.has(0 / 0)"),
+        err("Can't use NaN as a map key\n[line 1] in has()")
+    );
+}
+
+#[test]
+fn methods_keep_insertion_order() {
+    assert_eq!(eval("{\"b\": 1, \"a\": 2}.keys()"), ok("[b, a]"));
+    assert_eq!(eval("{\"b\": 1, \"a\": 2}.values()"), ok("[1, 2]"));
+    assert_eq!(
+        eval("{\"b\": 1, \"a\": 2}.entries()"),
+        ok("[[b, 1], [a, 2]]")
+    );
+}
+
+#[test]
+fn mutation() {
+    let results = eval_session(
+        &[("map", "{\"a\": 1}")],
+        &[
+            "map[\"b\"] = 2",
+            "map.has(\"b\")",
+            "map.remove(\"a\")",
+            "map.remove(\"a\")",
+            "map.has(\"a\")",
+            "map.len()",
+            "map",
+        ],
+    );
+    assert_eq!(
+        results,
+        [
+            ok("2"),
+            ok("true"),
+            ok("1"),
+            ok("nil"),
+            ok("false"),
+            ok("1"),
+            ok("{b: 2}")
+        ]
+    );
+}
+
+#[test]
+fn cycles() {
+    let results = eval_session(&[("map", "{}")], &["map[\"self\"] = map", "map", "[map]"]);
+    assert_eq!(
+        results,
+        [
+            ok("{self: {...}}"),
+            ok("{self: {...}}"),
+            ok("[{self: {...}}]")
+        ]
+    );
+}
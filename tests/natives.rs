@@ -0,0 +1,87 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use common::{err, lox, ok};
+use rust_lox::{Backend, FromLox, IntoLox, Lox, RuntimeError, SharedBuffer, Value};
+
+const BACKENDS: [Backend; 2] = [Backend::TreeWalker, Backend::Vm];
+
+fn eval(lox: &mut Lox, source: &str) -> Result<String, String> {
+    lox.eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn scripts_call_natives() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        lox.define_native("add", 2, |_, arguments| {
+            let (a, b) = (f64::from_lox(&arguments[0])?, f64::from_lox(&arguments[1])?);
+            Ok((a + b).into_lox())
+        });
+        lox.define_native("double", 1, |_, arguments| {
+            Ok((f64::from_lox(&arguments[0])? * 2.0).into_lox())
+        });
+
+        assert_eq!(eval(&mut lox, "add(1, 2) * double(3)"), ok("18"));
+        assert_eq!(eval(&mut lox, "[1, 2].map(double)"), ok("[2, 4]"));
+        assert_eq!(eval(&mut lox, "type(add)"), ok("function"));
+        assert_eq!(
+            eval(&mut lox, "add(1)"),
+            err("Expected 2 arguments but got 1: RightParen on line 1")
+        );
+        assert_eq!(
+            eval(&mut lox, "add(1, \"x\")"),
+            err("Expected number but got string\n[line 1] in add()")
+        );
+    }
+}
+
+#[test]
+fn natives_keep_host_state() {
+    for backend in BACKENDS {
+        let count = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&count);
+        let mut lox = lox(backend);
+        lox.define_native("tick", 0, move |_, _| {
+            counter.set(counter.get() + 1);
+            Ok(Value::Number(counter.get() as f64))
+        });
+
+        assert_eq!(eval(&mut lox, "tick() + tick()"), ok("3"));
+        assert_eq!(count.get(), 2);
+    }
+}
+
+#[test]
+fn errors_from_natives_reach_the_script() {
+    for backend in BACKENDS {
+        let mut lox = lox(backend);
+        lox.define_native("fail", 1, |_, arguments| {
+            Err(RuntimeError::Panic(String::from_lox(&arguments[0])?))
+        });
+
+        assert_eq!(
+            eval(&mut lox, "[\"boom\"].map(fail)"),
+            err("Panic: boom\n[line 1] in map()")
+        );
+    }
+}
+
+#[test]
+fn natives_can_be_replaced() {
+    for backend in BACKENDS {
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .native("len", 1, |_, _| Ok(Value::Number(-1.0)))
+            .stdout(SharedBuffer::new())
+            .build();
+        assert_eq!(eval(&mut lox, "len(\"abc\")"), ok("-1"));
+
+        lox.define_native("len", 1, |_, _| Ok(Value::Nil));
+        assert_eq!(eval(&mut lox, "len(\"abc\")"), ok("nil"));
+    }
+}
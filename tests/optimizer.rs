@@ -0,0 +1,102 @@
+use rust_lox::{Backend, Lox, SharedBuffer, Value};
+
+// Returns the tree the optimizer produced along with the result of running it.
+fn optimize(source: &str) -> (String, Result<String, String>) {
+    let stdout = SharedBuffer::new();
+    let mut lox = Lox::builder()
+        .optimize(true)
+        .dump_ast(true)
+        .global("a", Value::Number(1.0))
+        .global("s", Value::String("s".into()))
+        .stdout(stdout.clone())
+        .build();
+    let result = lox
+        .eval(source)
+        .map(|value| value.to_string())
+        .map_err(|error| error.to_string());
+    (stdout.take().trim_end().to_string(), result)
+}
+
+fn tree(source: &str) -> String {
+    optimize(source).0
+}
+
+#[test]
+fn trees_print_as_s_expressions() {
+    let stdout = SharedBuffer::new();
+    let mut lox = Lox::builder().dump_ast(true).stdout(stdout.clone()).build();
+    let _ = lox.eval("!x == -(1 + 2)");
+    assert_eq!(stdout.take(), "(== (! x) (- (group (+ 1 2))))\n");
+}
+
+#[test]
+fn constants_are_folded() {
+    assert_eq!(tree("1 + 2 * 3"), "7");
+    assert_eq!(tree("-(1 - 3)"), "2");
+    assert_eq!(tree("!(1 < 2)"), "false");
+    assert_eq!(tree("\"a\" + \"b\""), "ab");
+    assert_eq!(tree("\"x${1 + 1}y\""), "x2y");
+    assert_eq!(tree("[1 + 1, a + (2 * 2)]"), "(list 2 (+ a 4))");
+}
+
+// Folding must not change what a script does, so expressions that fail are
+// left for the runtime to report.
+#[test]
+fn failing_constants_are_left_alone() {
+    assert_eq!(
+        optimize("1 + nil"),
+        (
+            "(+ 1 nil)".to_string(),
+            Err("Invalid operand: Plus on line 1".to_string())
+        )
+    );
+    assert_eq!(tree("-\"x\""), "(- x)");
+}
+
+#[test]
+fn double_negations_are_removed_only_when_safe() {
+    assert_eq!(tree("!!(a == 1)"), "(== a 1)");
+    assert_eq!(tree("-(-(a * 2))"), "(* a 2)");
+
+    // `!!a` turns any value into a boolean, and `-(-s)` fails on strings, so
+    // neither is the same as the bare variable.
+    assert_eq!(
+        optimize("!!a"),
+        ("(! (! a))".to_string(), Ok("true".to_string()))
+    );
+    let (tree, result) = optimize("-(-s)");
+    assert_eq!(tree, "(- (- s))");
+    assert!(result.is_err());
+}
+
+#[test]
+fn optimizing_preserves_results() {
+    let sources = [
+        "1 + 2 * 3 - 4 / 2",
+        "!!(2 > 1) == !(1 >= 2)",
+        "-(-(3 * 3))",
+        "\"n = ${1 + 2}\"",
+        "[1 + 1, \"a\" + \"b\"][1]",
+        "{\"k\": 2 * 2}[\"k\"]",
+        "1 + nil",
+        "-\"x\"",
+    ];
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        for source in sources {
+            let results: Vec<Result<String, String>> = [false, true]
+                .into_iter()
+                .map(|optimize| {
+                    Lox::builder()
+                        .backend(backend)
+                        .optimize(optimize)
+                        .stdout(SharedBuffer::new())
+                        .build()
+                        .eval(source)
+                        .map(|value| value.to_string())
+                        .map_err(|error| error.to_string())
+                })
+                .collect();
+            assert_eq!(results[0], results[1], "{}", source);
+        }
+    }
+}
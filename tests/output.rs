@@ -0,0 +1,77 @@
+mod common;
+
+use std::fs;
+use std::io::{self, Write};
+
+use common::script;
+use rust_lox::{Backend, Error, Lox, SharedBuffer};
+
+// A sink that fails every write, like stdout once the reader has gone away.
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+#[test]
+fn output_goes_to_the_sinks() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let (stdout, stderr) = (SharedBuffer::new(), SharedBuffer::new());
+        let mut lox = Lox::builder()
+            .backend(backend)
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .build();
+
+        lox.run("\"a\" + \"b\"");
+        assert_eq!(stdout.take(), "ab\n");
+        lox.run("nope");
+        assert_eq!(
+            stderr.take(),
+            "Undefined variable: Identifier(\"nope\") on line 1\n"
+        );
+        assert_eq!(stdout.contents(), "");
+    }
+}
+
+#[test]
+fn write_errors_are_returned() {
+    let path = script("write-errors", "1 + 2");
+    let path = path.to_str().unwrap();
+
+    let mut lox = Lox::builder().stdout(Broken).build();
+    assert!(matches!(lox.run_file(path), Err(Error::Write(_))));
+
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .trace(true)
+        .stdout(Broken)
+        .build();
+    assert!(matches!(lox.eval("1 + 2"), Err(Error::Write(_))));
+
+    let stdout = SharedBuffer::new();
+    let mut lox = Lox::builder()
+        .backend(Backend::Vm)
+        .gc_stats(true)
+        .stdout(stdout.clone())
+        .stderr(Broken)
+        .build();
+    assert!(matches!(lox.run_file(path), Err(Error::Write(_))));
+    assert_eq!(stdout.contents(), "3\n");
+
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn failing_to_report_an_error_is_ignored() {
+    let mut lox = Lox::builder().stdout(Broken).stderr(Broken).build();
+    lox.run("1");
+    lox.run("nope");
+    lox.error(&"lost");
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{err, eval, ok};
+
+#[test]
+fn characters_not_bytes() {
+    assert_eq!(eval(r#""héllo".len()"#), ok("5"));
+    assert_eq!(eval(r#""héllo"[1]"#), ok("é"));
+    assert_eq!(
+        eval(r#""héllo"[5]"#),
+        err("Index 5 is out of range for length 5")
+    );
+    assert_eq!(
+        eval(r#""abc"[0.5]"#),
+        err("Index must be an integer but got 0.5")
+    );
+}
+
+#[test]
+fn methods() {
+    assert_eq!(eval(r#""hello".substring(1, 3)"#), ok("el"));
+    assert_eq!(eval(r#""hello".substring(3, 1)"#), ok(""));
+    assert_eq!(eval(r#""hello".substring(0, 9)"#), ok("hello"));
+    assert_eq!(
+        eval(r#""hello".substring(-1, 2)"#),
+        err("Index -1 is out of range for length 5\n[line 1] in substring()")
+    );
+    assert_eq!(
+        eval(r#""hello".substring(0.5, 2)"#),
+        err("Index must be an integer but got 0.5\n[line 1] in substring()")
+    );
+    assert_eq!(eval(r#""héllo".indexOf("l")"#), ok("2"));
+    assert_eq!(eval(r#""abc".indexOf("z")"#), ok("-1"));
+    assert_eq!(eval(r#""  a b  ".trim()"#), ok("a b"));
+    assert_eq!(eval(r#""aBc".upper() + "aBc".lower()"#), ok("ABCabc"));
+    assert_eq!(eval(r#""a-b-a".replace("a", "x")"#), ok("x-b-x"));
+    assert_eq!(eval(r#""hello".startsWith("he")"#), ok("true"));
+    assert_eq!(eval(r#""hello".endsWith("he")"#), ok("false"));
+    assert_eq!(eval(r#""a,b".split(",")"#), ok("[a, b]"));
+    assert_eq!(eval(r#""abc".split("")"#), ok("[a, b, c]"));
+}